@@ -0,0 +1,153 @@
+use quote::quote;
+use proc_macro2::{Ident, Span, TokenStream};
+use syn::{Attribute, Meta, NestedMeta, TypePath, Visibility, parse::Error};
+
+const INVALID_BITFIELD_ARGS: &str = "Expected #[bitfield] or #[bitfield(builder)].";
+
+/// Checks arguments of the #[bitfield(...)] attribute, returns whether the builder was requested.
+pub fn builder_requested_by_args(args: TokenStream) -> Result<bool, TokenStream> {
+    if args.is_empty() {
+        return Ok(false);
+    }
+
+    match syn::parse2::<Ident>(args) {
+        Ok(ident) if ident == "builder" => Ok(true),
+        Ok(ident) => Err(Error::new_spanned(ident, INVALID_BITFIELD_ARGS).to_compile_error()),
+        Err(e) => Err(Error::new(e.span(), INVALID_BITFIELD_ARGS).to_compile_error()),
+    }
+}
+
+/// Checks whether #[derive(Builder)] of derive_builder is listed among the struct attributes.
+///
+/// The #[bitfield] macro replaces the fields of the struct, so the derive could not see them.
+/// Instead the builder is generated here and the derive is not emitted. A derive written above
+/// #[bitfield] is expanded before this macro runs, derive_builder rejects it in that case.
+///
+/// Macros can't resolve names, so only `Builder` and `derive_builder::Builder` are recognized,
+/// a `Builder` derive of another crate written with the path of its crate gets no builder.
+pub fn builder_requested_by_derive(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("derive"))
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::List(l)) => Some(l.nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(p)) => {
+                let path = p.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>();
+                path == ["Builder"] || path == ["derive_builder", "Builder"]
+            }
+            _ => false,
+        })
+}
+
+fn builder_ident(name: &Ident) -> Ident {
+    Ident::new(&format!("{}Builder", name), Span::call_site())
+}
+
+fn builder_error_ident(name: &Ident) -> Ident {
+    Ident::new(&format!("{}BuilderError", name), Span::call_site())
+}
+
+/// Generates the builder collecting value of every field and assembling the packed bitfield.
+///
+/// Values are kept as u64 so that `build()` can check whether they fit into the bits of the field
+/// before writing them.
+pub fn builder(vis: &Visibility, name: &Ident, fields: &[(&Ident, &TypePath)]) -> TokenStream {
+    let builder_ident = builder_ident(name);
+    let error_ident = builder_error_ident(name);
+    let fields_len = fields.len();
+    let owner = quote! { #name };
+    let data = quote! { bitfield.data };
+
+    let idents = fields.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+    let in_tys = fields
+        .iter()
+        .map(|(_, ty)| quote! { <#ty as bitfield::Specifier>::InOutType });
+    let writes = fields.iter().enumerate().map(|(field_index, (ident, ty))| {
+        let name = ident.to_string();
+        let write = crate::methods::write_bits(&owner, &data, fields_len, field_index);
+
+        quote! {
+            let val = match self.#ident.take() {
+                ::std::option::Option::Some(v) => v,
+                ::std::option::Option::None => return ::std::result::Result::Err(#error_ident::MissingField(#name)),
+            };
+            let bits = <#ty as bitfield::Specifier>::BITS;
+
+            if val.checked_shr(bits as u32).unwrap_or(0) != 0 {
+                return ::std::result::Result::Err(#error_ident::OutOfRange { field: #name, bits, value: val });
+            }
+
+            { #write }
+        }
+    });
+
+    let error = builder_error(vis, &error_ident);
+
+    quote! {
+        #vis struct #builder_ident {
+            #(#idents: ::std::option::Option<u64>,)*
+        }
+
+        impl #builder_ident {
+            fn new() -> Self {
+                Self {
+                    #(#idents: ::std::option::Option::None,)*
+                }
+            }
+
+            #(
+                fn #idents(&mut self, value: #in_tys) -> &mut Self {
+                    self.#idents = ::std::option::Option::Some(value as u64);
+                    self
+                }
+            )*
+
+            fn build(&mut self) -> ::std::result::Result<#name, #error_ident> {
+                let mut bitfield = #name::new();
+
+                #(#writes)*
+
+                ::std::result::Result::Ok(bitfield)
+            }
+        }
+
+        impl #name {
+            fn builder() -> #builder_ident {
+                #builder_ident::new()
+            }
+        }
+
+        #error
+    }
+}
+
+fn builder_error(vis: &Visibility, error_ident: &Ident) -> TokenStream {
+    quote! {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #vis enum #error_ident {
+            MissingField(&'static str),
+            OutOfRange {
+                field: &'static str,
+                bits: u8,
+                value: u64,
+            },
+        }
+
+        impl ::std::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    Self::MissingField(field) => write!(f, "field `{}` is not set", field),
+                    Self::OutOfRange { field, bits, value } => {
+                        write!(f, "value {} of field `{}` does not fit in {} bits", value, field, bits)
+                    }
+                }
+            }
+        }
+
+        impl ::std::error::Error for #error_ident {}
+    }
+}
@@ -28,7 +28,9 @@ pub fn get_field_type(field: &Field) -> Result<&TypePath, TokenStream> {
     }
 }
 
-pub fn get_struct_fields(input: & DeriveInput) -> Result<(Vec<&Field>, Vec<(&Ident, &TypePath)>), TokenStream> {
+type StructFields<'a> = (Vec<&'a Field>, Vec<(&'a Ident, &'a TypePath)>);
+
+pub fn get_struct_fields(input: & DeriveInput) -> Result<StructFields<'_>, TokenStream> {
     let struct_fields = match input.data {
         Data::Struct(ref d) => &d.fields,
         _ => return Err(Error::new_spanned(input, INVALID_BITFIELD_ATTR_INPUT).to_compile_error()),
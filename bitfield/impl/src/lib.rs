@@ -1,10 +1,9 @@
-#![feature(int_log)]
-
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::quote;
 use syn::{parse_macro_input, parse::Error, DeriveInput};
 
+mod builder;
 mod fields;
 mod idents;
 mod size;
@@ -18,11 +17,10 @@ pub fn bits(_args: TokenStream, input: TokenStream) -> TokenStream {
 
 #[proc_macro_attribute]
 pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
-    if !args.is_empty() {
-        return Error::new(Span::call_site(), "#[bitfield] macro does not accept any arguments.")
-            .to_compile_error()
-            .into();
-    }
+    let builder_by_args = match builder::builder_requested_by_args(args.into()) {
+        Ok(b) => b,
+        Err(e) => return e.into(),
+    };
 
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -42,6 +40,14 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
     let getters = methods::getters(&fields_name_type);
     let setters = methods::setters(&fields_name_type);
     let bit_attr_checks = traits::impl_bits_checks(name, fields);
+    // #[derive(Builder)] below this attribute is taken over rather than passed through with
+    // the struct: the derive would see the packed `data` array instead of the declared fields
+    // and generate setters for it, while the builder here sets each field through its bits.
+    let builder = if builder_by_args || builder::builder_requested_by_derive(&input.attrs) {
+        builder::builder(vis, name, &fields_name_type)
+    } else {
+        quote! {}
+    };
 
     quote! {
         const #size_const: usize = #bitfield_size;
@@ -68,6 +74,7 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
 
         #checks_impl
         #bit_attr_checks
+        #builder
     }.into()
 }
 
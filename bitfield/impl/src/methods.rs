@@ -38,23 +38,13 @@ pub fn setters(fields: &[(&Ident, &TypePath)]) -> TokenStream {
         let setter = Ident::new(&format!("set_{}", field_name), Span::call_site());
         let in_ty = quote! { <#ty as bitfield::Specifier>::InOutType };
 
+        let write = write_bits(&quote! { Self }, &quote! { self.data }, fields_len, field_index);
+
         out.push(quote! {
             fn #setter(&mut self, value: #in_ty) -> &mut Self {
-                let [start, len] = <Self as bitfield::Bitfield<#fields_len>>::BIT_INDICES[#field_index];
-
-                let mut right_shift = 0;
                 let val = value as u64;
 
-                for i in (start..(start + len)).rev() {
-                    let data_byte = self.data[i / 8];
-                    let mut left_shift = 8 - (i % 8) - 1;
-                    
-                    self.data[i / 8] = data_byte | (
-                        (((val >> right_shift) as u8) & 0b1) << left_shift
-                    );
-
-                    right_shift += 1;
-                }
+                #write
 
                 self
             }
@@ -63,3 +53,24 @@ pub fn setters(fields: &[(&Ident, &TypePath)]) -> TokenStream {
 
     TokenStream::from_iter(out)
 }
+
+/// Writes the lowest bits of `val: u64` into the bits of the field at `field_index` in `data`
+/// of the `owner` bitfield.
+pub fn write_bits(owner: &TokenStream, data: &TokenStream, fields_len: usize, field_index: usize) -> TokenStream {
+    quote! {
+        let [start, len] = <#owner as bitfield::Bitfield<#fields_len>>::BIT_INDICES[#field_index];
+
+        let mut right_shift = 0;
+
+        for i in (start..(start + len)).rev() {
+            let data_byte = #data[i / 8];
+            let mut left_shift = 8 - (i % 8) - 1;
+
+            #data[i / 8] = data_byte | (
+                (((val >> right_shift) as u8) & 0b1) << left_shift
+            );
+
+            right_shift += 1;
+        }
+    }
+}
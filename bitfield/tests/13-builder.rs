@@ -0,0 +1,109 @@
+// Building a bitfield one setter at a time is easy to get wrong, a field which
+// was forgotten silently stays zero and a value which is too large for the
+// field gets truncated. Generate a builder for the bitfield if it is requested
+// with #[bitfield(builder)] or with #[derive(Builder)] on the struct.
+//
+// The builder has a setter for every field taking the same type as the setter
+// of the bitfield, and a `build` method which assembles the packed value. It
+// fails if some field was not set or if a value does not fit into the bits of
+// its field. The #[derive(Builder)] of derive_builder below #[bitfield] is
+// taken over by the bitfield, as that derive would only see the packed bytes.
+//
+//     let entry = RedirectionTableEntry::builder()
+//         .acknowledged(true)
+//         .trigger_mode(TriggerMode::Level)
+//         .delivery_mode(DeliveryMode::SMI)
+//         .reserved(0)
+//         .build()?;
+
+use bitfield::*;
+use derive_builder::Builder;
+
+#[bitfield(builder)]
+pub struct RedirectionTableEntry {
+    acknowledged: bool,
+    trigger_mode: TriggerMode,
+    delivery_mode: DeliveryMode,
+    reserved: B3,
+}
+
+#[derive(BitfieldSpecifier, Debug, PartialEq)]
+pub enum TriggerMode {
+    Edge = 0,
+    Level = 1,
+}
+
+#[derive(BitfieldSpecifier, Debug, PartialEq)]
+pub enum DeliveryMode {
+    Fixed = 0b000,
+    Lowest = 0b001,
+    SMI = 0b010,
+    RemoteRead = 0b011,
+    NMI = 0b100,
+    Init = 0b101,
+    Startup = 0b110,
+    External = 0b111,
+}
+
+#[bitfield]
+#[derive(Builder)]
+pub struct EdgeCaseBytes {
+    a: B9,
+    b: B6,
+    c: B13,
+    d: B4,
+}
+
+fn main() {
+    let entry = RedirectionTableEntry::builder()
+        .acknowledged(true)
+        .trigger_mode(TriggerMode::Level)
+        .delivery_mode(DeliveryMode::SMI)
+        .reserved(0b101)
+        .build()
+        .unwrap();
+
+    assert!(entry.get_acknowledged());
+    assert_eq!(entry.get_trigger_mode(), TriggerMode::Level);
+    assert_eq!(entry.get_delivery_mode(), DeliveryMode::SMI);
+    assert_eq!(entry.get_reserved(), 0b101);
+
+    let err = RedirectionTableEntry::builder()
+        .acknowledged(false)
+        .trigger_mode(TriggerMode::Edge)
+        .reserved(0)
+        .build()
+        .unwrap_err();
+    assert_eq!(err, RedirectionTableEntryBuilderError::MissingField("delivery_mode"));
+    assert_eq!(err.to_string(), "field `delivery_mode` is not set");
+
+    let err = RedirectionTableEntry::builder()
+        .acknowledged(false)
+        .trigger_mode(TriggerMode::Edge)
+        .delivery_mode(DeliveryMode::Fixed)
+        .reserved(0b1000)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RedirectionTableEntryBuilderError::OutOfRange {
+            field: "reserved",
+            bits: 3,
+            value: 8,
+        }
+    );
+    assert_eq!(err.to_string(), "value 8 of field `reserved` does not fit in 3 bits");
+
+    let bitfield = EdgeCaseBytes::builder()
+        .a(0b1100_0011_1)
+        .b(0b101_010)
+        .c(0x1675)
+        .d(0b1110)
+        .build()
+        .unwrap();
+
+    assert_eq!(bitfield.get_a(), 0b1100_0011_1);
+    assert_eq!(bitfield.get_b(), 0b101_010);
+    assert_eq!(bitfield.get_c(), 0x1675);
+    assert_eq!(bitfield.get_d(), 0b1110);
+}
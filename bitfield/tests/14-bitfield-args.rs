@@ -0,0 +1,13 @@
+// The only argument accepted by the #[bitfield] attribute is `builder`.
+
+use bitfield::*;
+
+#[bitfield(builer)]
+pub struct MyFourBytes {
+    a: B1,
+    b: B3,
+    c: B4,
+    d: B24,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+// The #[derive(Builder)] of derive_builder written above #[bitfield] runs
+// before the fields of the struct are packed, so it would generate a builder
+// for fields which are gone. Point to #[bitfield(builder)] instead.
+
+use bitfield::*;
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[bitfield]
+pub struct Packed {
+    a: B4,
+    b: B4,
+}
+
+fn main() {}
@@ -13,4 +13,7 @@ fn tests() {
     t.pass("tests/10-bits-attribute.rs");
     t.compile_fail("tests/11-bits-attribute-wrong.rs");
     t.pass("tests/12-accessors-edge.rs");
+    t.pass("tests/13-builder.rs");
+    t.compile_fail("tests/14-bitfield-args.rs");
+    t.compile_fail("tests/15-derive-builder-above.rs");
 }
@@ -0,0 +1,346 @@
+use proc_macro2::{Ident, TokenStream};
+use syn::{parse::Error, Attribute, Expr, Lit, LitStr, Meta, MetaList, NestedMeta, Type};
+
+const UNRECOGNIZED: &str = "Unrecognized argument to builder attribute";
+
+const EXPECTED_CONTAINER_ATTR: &str =
+    "expected `builder(exclusive(...))` or `builder(require_one_of(...))`";
+
+// Options accepted in #[builder(...)] attribute on a struct field.
+const FIELD_KEYS: &[&str] = &["each", "env", "env_separator", "field", "default_with"];
+
+// Options accepted in #[builder(field(...))] attribute.
+const FIELD_STORAGE_KEYS: &[&str] = &["ty", "build", "try_build"];
+
+// Options accepted in #[builder(...)] attribute on a struct.
+const CONTAINER_KEYS: &[&str] = &[
+    "exclusive",
+    "require_one_of",
+    "env_prefix",
+    "env_separator",
+    "set_by_name",
+    "const",
+];
+
+// Options given with #[builder(...)] attributes on a struct field.
+#[derive(Default)]
+pub struct FieldAttrs {
+    // #[builder(each = "...")]
+    pub each: Option<Ident>,
+    // #[builder(env = "...")]
+    pub env: Option<LitStr>,
+    // #[builder(env_separator = "...")]
+    pub env_separator: Option<LitStr>,
+    // #[builder(field(ty = "..."))]
+    pub field_ty: Option<Type>,
+    // #[builder(field(build = "..."))] or #[builder(field(try_build = "..."))]
+    pub field_build: Option<FieldBuild>,
+    // #[builder(default_with = "...")], expression may refer to values of other fields by name.
+    pub default_with: Option<(LitStr, Expr)>,
+}
+
+// Expression turning value collected by the builder into the value of the field.
+// The collected value is available under the name of the field.
+pub enum FieldBuild {
+    // #[builder(field(build = "..."))]
+    Value(Expr),
+    // #[builder(field(try_build = "..."))], expression returns Result with Display error.
+    Try(Expr),
+}
+
+// Constraint on a group of fields declared with container attribute.
+//
+// #[builder(exclusive(a, b))]      - at most one of the fields may be set.
+// #[builder(require_one_of(a, b))] - at least one of the fields must be set.
+pub enum FieldGroup {
+    Exclusive(Vec<Ident>),
+    RequireOneOf(Vec<Ident>),
+}
+
+// Options given with #[builder(...)] attributes on a struct.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub groups: Vec<FieldGroup>,
+    // #[builder(env_prefix = "...")]
+    pub env_prefix: Option<LitStr>,
+    // #[builder(env_separator = "...")]
+    pub env_separator: Option<LitStr>,
+    // #[builder(set_by_name)]
+    pub set_by_name: bool,
+    // #[builder(const)]
+    pub is_const: bool,
+}
+
+// Get every #[builder(...)] attribute together with the list of its arguments.
+fn get_builder_lists(attrs: &[Attribute]) -> Result<Vec<(MetaList, Vec<Meta>)>, TokenStream> {
+    let mut lists = Vec::new();
+
+    for attr in attrs {
+        if !attr.path.is_ident("builder") {
+            continue;
+        }
+
+        let list = match attr.parse_meta() {
+            Ok(Meta::List(l)) => l,
+            Ok(m) => return Err(Error::new_spanned(m, UNRECOGNIZED).to_compile_error()),
+            Err(_) => return Err(Error::new_spanned(attr, UNRECOGNIZED).to_compile_error()),
+        };
+
+        let mut metas = Vec::new();
+
+        for nested in &list.nested {
+            match nested {
+                NestedMeta::Meta(m) => metas.push(m.clone()),
+                un => return Err(Error::new_spanned(un, UNRECOGNIZED).to_compile_error()),
+            }
+        }
+
+        lists.push((list, metas));
+    }
+
+    Ok(lists)
+}
+
+// Get number of single character edits needed to turn one string into another.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            let insertion = cur[j] + 1;
+            let deletion = prev[j + 1] + 1;
+
+            cur.push(substitution.min(insertion).min(deletion));
+        }
+
+        prev = cur;
+    }
+
+    prev[b.len()]
+}
+
+// Get error reported at the span of unknown option.
+// Suggests the closest of the known options if the name looks like a misspelling of it.
+fn unknown_key(meta: &Meta, known: &[&str]) -> TokenStream {
+    use quote::ToTokens;
+
+    let path = meta.path();
+    let name = path.to_token_stream().to_string().replace(' ', "");
+
+    let closest = known
+        .iter()
+        .map(|k| (edit_distance(&name, k), k))
+        .min_by_key(|(d, _)| *d)
+        .filter(|(d, k)| *d <= 2 && *d < k.len());
+
+    let msg = match closest {
+        Some((_, k)) => format!("unknown builder option '{}', did you mean '{}'?", name, k),
+        None => format!(
+            "unknown builder option '{}', expected one of: {}",
+            name,
+            known.join(", ")
+        ),
+    };
+
+    Error::new_spanned(path, msg).to_compile_error()
+}
+
+// Get string literal out of `key = "..."` argument.
+fn get_str(meta: &Meta, msg: &str, span: &MetaList) -> Result<LitStr, TokenStream> {
+    match meta {
+        Meta::NameValue(nv) => match nv.lit {
+            Lit::Str(ref s) => Ok(s.clone()),
+            _ => Err(Error::new_spanned(span, msg).to_compile_error()),
+        },
+        _ => Err(Error::new_spanned(span, msg).to_compile_error()),
+    }
+}
+
+// Check that argument is a bare `key` without any value.
+fn get_flag(meta: &Meta, msg: &str) -> Result<(), TokenStream> {
+    match meta {
+        Meta::Path(_) => Ok(()),
+        un => Err(Error::new_spanned(un, msg).to_compile_error()),
+    }
+}
+
+// Set option which may be given only once.
+fn set_once<T>(slot: &mut Option<T>, value: T, meta: &Meta) -> Result<(), TokenStream> {
+    if slot.is_some() {
+        return Err(Error::new_spanned(meta.path(), "duplicated builder option").to_compile_error());
+    }
+
+    slot.replace(value);
+
+    Ok(())
+}
+
+pub fn get_field_attrs(attrs: &[Attribute]) -> Result<FieldAttrs, TokenStream> {
+    let mut result = FieldAttrs::default();
+
+    for (list, metas) in get_builder_lists(attrs)? {
+        for meta in &metas {
+            let path = meta.path();
+
+            if path.is_ident("each") {
+                let name = get_str(meta, r#"expected `builder(each = "...")`"#, &list)?;
+                let ident = Ident::new(&name.value(), name.span());
+                set_once(&mut result.each, ident, meta)?;
+            } else if path.is_ident("env") {
+                let name = get_str(meta, r#"expected `builder(env = "...")`"#, &list)?;
+                set_once(&mut result.env, name, meta)?;
+            } else if path.is_ident("env_separator") {
+                let sep = get_str(meta, r#"expected `builder(env_separator = "...")`"#, &list)?;
+                set_once(&mut result.env_separator, sep, meta)?;
+            } else if path.is_ident("field") {
+                get_field_storage(meta, &mut result)?;
+            } else if path.is_ident("default_with") {
+                let lit = get_str(meta, r#"expected `builder(default_with = "...")`"#, &list)?;
+                let expr = lit.parse::<Expr>().map_err(|e| e.to_compile_error())?;
+                set_once(&mut result.default_with, (lit, expr), meta)?;
+            } else {
+                return Err(unknown_key(meta, FIELD_KEYS));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// Get options out of #[builder(field(ty = "...", build = "..."))].
+fn get_field_storage(meta: &Meta, result: &mut FieldAttrs) -> Result<(), TokenStream> {
+    const EXPECTED: &str = r#"expected `builder(field(ty = "...", build = "..."))`"#;
+
+    let list = match meta {
+        Meta::List(l) if !l.nested.is_empty() => l,
+        un => return Err(Error::new_spanned(un, EXPECTED).to_compile_error()),
+    };
+
+    for nested in &list.nested {
+        let meta = match nested {
+            NestedMeta::Meta(m) => m,
+            un => return Err(Error::new_spanned(un, EXPECTED).to_compile_error()),
+        };
+        let path = meta.path();
+
+        if !FIELD_STORAGE_KEYS.iter().any(|k| path.is_ident(k)) {
+            return Err(unknown_key(meta, FIELD_STORAGE_KEYS));
+        }
+
+        let value = get_str(meta, EXPECTED, list)?;
+
+        if path.is_ident("ty") {
+            let ty = value.parse::<Type>().map_err(|e| e.to_compile_error())?;
+            set_once(&mut result.field_ty, ty, meta)?;
+            continue;
+        }
+
+        let expr = value.parse::<Expr>().map_err(|e| e.to_compile_error())?;
+        let build = if path.is_ident("build") {
+            FieldBuild::Value(expr)
+        } else {
+            FieldBuild::Try(expr)
+        };
+
+        set_once(&mut result.field_build, build, meta)?;
+    }
+
+    Ok(())
+}
+
+pub fn get_container_attrs(
+    attrs: &[Attribute],
+    field_idents: &[&Ident],
+) -> Result<ContainerAttrs, TokenStream> {
+    let mut result = ContainerAttrs::default();
+
+    for (list, metas) in get_builder_lists(attrs)? {
+        for meta in &metas {
+            let path = meta.path();
+
+            if path.is_ident("env_prefix") {
+                let prefix = get_str(meta, r#"expected `builder(env_prefix = "...")`"#, &list)?;
+                set_once(&mut result.env_prefix, prefix, meta)?;
+                continue;
+            }
+
+            if path.is_ident("env_separator") {
+                let sep = get_str(meta, r#"expected `builder(env_separator = "...")`"#, &list)?;
+                set_once(&mut result.env_separator, sep, meta)?;
+                continue;
+            }
+
+            if path.is_ident("set_by_name") {
+                get_flag(meta, "expected `builder(set_by_name)`")?;
+                result.set_by_name = true;
+                continue;
+            }
+
+            if path.is_ident("const") {
+                get_flag(meta, "expected `builder(const)`")?;
+                result.is_const = true;
+                continue;
+            }
+
+            if !path.is_ident("exclusive") && !path.is_ident("require_one_of") {
+                return Err(unknown_key(meta, CONTAINER_KEYS));
+            }
+
+            let group = match meta {
+                Meta::List(g) => g,
+                un => {
+                    return Err(Error::new_spanned(un, EXPECTED_CONTAINER_ATTR).to_compile_error())
+                }
+            };
+
+            let idents = get_group_idents(group, field_idents)?;
+
+            if path.is_ident("exclusive") {
+                result.groups.push(FieldGroup::Exclusive(idents));
+            } else {
+                result.groups.push(FieldGroup::RequireOneOf(idents));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// Get list of field names out of `exclusive(...)` or `require_one_of(...)`.
+// Returns TokenStream with error if group is malformed or refers to unknown field.
+fn get_group_idents(group: &MetaList, field_idents: &[&Ident]) -> Result<Vec<Ident>, TokenStream> {
+    let mut idents = Vec::new();
+
+    for item in &group.nested {
+        let ident = match item {
+            NestedMeta::Meta(Meta::Path(p)) => p.get_ident(),
+            _ => None,
+        };
+
+        let ident = match ident {
+            Some(i) => i,
+            None => return Err(Error::new_spanned(item, "expected field name").to_compile_error()),
+        };
+
+        if !field_idents.contains(&ident) {
+            return Err(
+                Error::new_spanned(ident, format!("unknown field `{}`", ident)).to_compile_error(),
+            );
+        }
+
+        idents.push(ident.clone());
+    }
+
+    if idents.len() < 2 {
+        return Err(
+            Error::new_spanned(group, "field group requires at least two fields")
+                .to_compile_error(),
+        );
+    }
+
+    Ok(idents)
+}
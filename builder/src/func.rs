@@ -0,0 +1,502 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    parse::Error, visit_mut::VisitMut, Attribute, FnArg, GenericParam, Generics, ImplItem, ItemFn,
+    ItemImpl, Lifetime, Pat, ReturnType, Signature, Type,
+};
+
+use crate::{attrs, expand_builder, BuilderSpec, TargetField};
+
+// Lifetime given to the references which have their lifetime elided in the function signature.
+const BUILDER_LIFETIME: &str = "'__builder";
+
+// Replaces elided lifetimes with the builder lifetime and `Self` with the implementing type.
+// Builder stores the arguments in its fields, where lifetimes can not be elided.
+struct TypeFixer<'a> {
+    lifetime: Lifetime,
+    self_ty: Option<&'a Type>,
+    used: bool,
+}
+
+impl VisitMut for TypeFixer<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let (Some(self_ty), Type::Path(p)) = (self.self_ty, &*ty) {
+            if p.qself.is_none() && p.path.is_ident("Self") {
+                *ty = self_ty.clone();
+                return;
+            }
+        }
+
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_type_reference_mut(&mut self, r: &mut syn::TypeReference) {
+        if r.lifetime.is_none() {
+            r.lifetime = Some(self.lifetime.clone());
+            self.used = true;
+        }
+
+        syn::visit_mut::visit_type_reference_mut(self, r);
+    }
+
+    fn visit_lifetime_mut(&mut self, l: &mut Lifetime) {
+        if l.ident == "_" {
+            *l = self.lifetime.clone();
+            self.used = true;
+        }
+    }
+
+    // Elided lifetimes of fn pointers and Fn traits belong to them.
+    fn visit_type_bare_fn_mut(&mut self, _: &mut syn::TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        _: &mut syn::ParenthesizedGenericArguments,
+    ) {
+    }
+}
+
+// Function argument turned into the builder field.
+struct Arg {
+    ident: Ident,
+    ty: Type,
+    attrs: Vec<Attribute>,
+}
+
+// Get list of arguments out of the function signature, without the receiver.
+// Removes #[builder(...)] attributes from the arguments, as they are consumed by the macro.
+fn get_args(sig: &mut Signature, fixer: &mut TypeFixer) -> Result<Vec<Arg>, TokenStream> {
+    let mut args = Vec::new();
+
+    for input in sig.inputs.iter_mut() {
+        let pat_type = match input {
+            FnArg::Typed(t) => t,
+            FnArg::Receiver(_) => continue,
+        };
+
+        let ident = match *pat_type.pat {
+            Pat::Ident(ref i) if i.by_ref.is_none() && i.subpat.is_none() => i.ident.clone(),
+            ref pat => {
+                return Err(Error::new_spanned(pat, "expected argument name").to_compile_error())
+            }
+        };
+
+        if let Type::ImplTrait(ref t) = *pat_type.ty {
+            return Err(Error::new_spanned(
+                t,
+                "`impl Trait` arguments are not supported, use a generic parameter instead",
+            )
+            .to_compile_error());
+        }
+
+        let (attrs, rest) = pat_type
+            .attrs
+            .drain(..)
+            .partition(|a| a.path.is_ident("builder"));
+        pat_type.attrs = rest;
+
+        let mut ty = (*pat_type.ty).clone();
+        fixer.visit_type_mut(&mut ty);
+
+        args.push(Arg { ident, ty, attrs });
+    }
+
+    Ok(args)
+}
+
+// Get fields of the builder out of the function arguments.
+fn get_target_fields(args: &[Arg]) -> Result<Vec<TargetField<'_>>, TokenStream> {
+    args.iter()
+        .map(|a| {
+            Ok(TargetField {
+                ident: &a.ident,
+                ty: &a.ty,
+                attrs: attrs::get_field_attrs(&a.attrs)?,
+            })
+        })
+        .collect()
+}
+
+// Get options passed to the #[builder(...)] attribute of the function.
+fn get_container_attrs(
+    args: TokenStream,
+    fields: &[TargetField],
+) -> Result<attrs::ContainerAttrs, TokenStream> {
+    let attr: Attribute = syn::parse_quote!(#[builder(#args)]);
+    let idents = fields.iter().map(|f| f.ident).collect::<Vec<_>>();
+    let container = attrs::get_container_attrs(&[attr], &idents)?;
+
+    if container.is_const {
+        return Err(
+            Error::new_spanned(args, "`builder(const)` is not supported on functions")
+                .to_compile_error(),
+        );
+    }
+
+    Ok(container)
+}
+
+// Get return type of the function with lifetimes and `Self` fixed same as arguments.
+fn get_output(sig: &Signature, fixer: &mut TypeFixer) -> TokenStream {
+    match sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ref ty) => {
+            let mut ty = (**ty).clone();
+            fixer.visit_type_mut(&mut ty);
+            quote! { #ty }
+        }
+    }
+}
+
+// Get generics of the builder: builder lifetime (if used) followed by the given generics.
+fn get_builder_generics(fixer: &TypeFixer, generics: &[&Generics]) -> Generics {
+    use syn::{punctuated::Punctuated, LifetimeDef, WhereClause};
+
+    let mut result = Generics::default();
+
+    if fixer.used {
+        result.params.push(GenericParam::Lifetime(LifetimeDef::new(
+            fixer.lifetime.clone(),
+        )));
+    }
+
+    let mut predicates = Punctuated::new();
+
+    for g in generics {
+        // Lifetimes have to be declared before other parameters.
+        let (lifetimes, rest): (Vec<_>, Vec<_>) = g
+            .params
+            .iter()
+            .cloned()
+            .partition(|p| matches!(p, GenericParam::Lifetime(_)));
+
+        let at = result
+            .params
+            .iter()
+            .take_while(|p| matches!(p, GenericParam::Lifetime(_)))
+            .count();
+        let mut params = result.params.into_iter().collect::<Vec<_>>();
+        params.splice(at..at, lifetimes);
+        params.extend(rest);
+        result.params = params.into_iter().collect();
+
+        if let Some(ref w) = g.where_clause {
+            predicates.extend(w.predicates.iter().cloned());
+        }
+    }
+
+    if !predicates.is_empty() {
+        result.where_clause = Some(WhereClause {
+            where_token: Default::default(),
+            predicates,
+        });
+    }
+
+    result
+}
+
+// Get turbofish passing type and const parameters of the function explicitly,
+// as they may appear only in the return type.
+fn get_turbofish(generics: &Generics) -> TokenStream {
+    let params = generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            GenericParam::Type(t) => Some(&t.ident),
+            GenericParam::Const(c) => Some(&c.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    if params.is_empty() {
+        quote! {}
+    } else {
+        quote! { ::<#(#params),*> }
+    }
+}
+
+// Turn `snake_case` function name into `CamelCase`.
+fn camel_case(ident: &Ident) -> String {
+    use syn::ext::IdentExt;
+
+    ident
+        .unraw()
+        .to_string()
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// #[builder]
+// fn {name}({args}) -> {output} { .. }
+//
+// Turns into:
+//
+// fn {name}() -> {Name}Builder { {Name}Builder::new() }
+//
+// impl {Name}Builder {
+//     fn {arg} (&mut self, value: {arg_type}) -> &mut Self;
+//     ..
+//     fn call(&mut self) -> Result<{output}, {Name}BuilderError>;
+// }
+fn expand_fn(args: TokenStream, mut item: ItemFn) -> Result<TokenStream, TokenStream> {
+    let mut fixer = TypeFixer {
+        lifetime: Lifetime::new(BUILDER_LIFETIME, Span::call_site()),
+        self_ty: None,
+        used: false,
+    };
+
+    let fn_args = get_args(&mut item.sig, &mut fixer)?;
+    let fields = get_target_fields(&fn_args)?;
+    let container = get_container_attrs(args, &fields)?;
+    let finish_output = get_output(&item.sig, &mut fixer);
+    let generics = get_builder_generics(&fixer, &[&item.sig.generics]);
+
+    let ItemFn {
+        attrs, vis, sig, ..
+    } = &item;
+    let name = &sig.ident;
+
+    // Original function is called from the inside of the `call` method.
+    let mut inner = item.clone();
+    inner.vis = syn::Visibility::Inherited;
+    inner
+        .attrs
+        .retain(|a| a.path.is_ident("allow") || a.path.is_ident("cfg"));
+
+    let base_ident = Ident::new(&camel_case(name), name.span());
+    let builder_ident = crate::create_builder_ident(&base_ident);
+    let idents = fields.iter().map(|f| f.ident.clone()).collect::<Vec<_>>();
+    let turbofish = get_turbofish(&sig.generics);
+    let asyncness = &sig.asyncness;
+    let unsafety = &sig.unsafety;
+    let awaiting = asyncness.map(|_| quote! { .await });
+
+    let builder = expand_builder(&BuilderSpec {
+        base_ident: base_ident.clone(),
+        vis,
+        generics: generics.clone(),
+        container,
+        fields,
+        extra_fields: Vec::new(),
+        finish: quote! { #asyncness #unsafety fn call },
+        finish_output,
+        finish_expr: quote! {
+            #unsafety {
+                #inner
+
+                #name #turbofish (#(#idents),*) #awaiting
+            }
+        },
+    })?;
+
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis fn #name #generics () -> #builder_ident #ty_generics #where_clause {
+            #builder_ident::new()
+        }
+
+        #builder
+    })
+}
+
+// #[builder]
+// impl {Type} {
+//     #[builder]
+//     fn {name}(&self, {args}) -> {output} { .. }
+// }
+//
+// Turns into:
+//
+// impl {Type} {
+//     fn {name}(&self) -> {Type}{Name}Builder { {Type}{Name}Builder::new(self) }
+//
+//     fn __builder_{name}(&self, {args}) -> {output} { .. }
+// }
+//
+// impl {Type}{Name}Builder {
+//     fn {arg} (&mut self, value: {arg_type}) -> &mut Self;
+//     ..
+//     fn call(&mut self) -> Result<{output}, {Type}{Name}BuilderError>;
+// }
+fn expand_impl(args: TokenStream, mut item: ItemImpl) -> Result<TokenStream, TokenStream> {
+    if !args.is_empty() {
+        return Err(Error::new_spanned(
+            args,
+            "options of `#[builder]` on impl block go on its methods",
+        )
+        .to_compile_error());
+    }
+
+    if let Some((_, ref path, _)) = item.trait_ {
+        return Err(Error::new_spanned(
+            path,
+            "`#[builder]` is supported only on inherent impl blocks",
+        )
+        .to_compile_error());
+    }
+
+    let self_name = match *item.self_ty {
+        Type::Path(ref p) if p.qself.is_none() => p.path.segments.last().map(|s| s.ident.clone()),
+        _ => None,
+    };
+
+    let self_name = match self_name {
+        Some(n) => n,
+        None => {
+            return Err(Error::new_spanned(&item.self_ty, "expected type name").to_compile_error())
+        }
+    };
+
+    let mut items = Vec::new();
+    let mut builders = Vec::new();
+
+    for impl_item in std::mem::take(&mut item.items) {
+        let mut method = match impl_item {
+            ImplItem::Method(m) => m,
+            other => {
+                items.push(other);
+                continue;
+            }
+        };
+
+        let at = match method.attrs.iter().position(|a| a.path.is_ident("builder")) {
+            Some(at) => at,
+            None => {
+                items.push(ImplItem::Method(method));
+                continue;
+            }
+        };
+
+        let marker = method.attrs.remove(at);
+        let method_args = match marker.parse_meta() {
+            Ok(syn::Meta::Path(_)) => TokenStream::new(),
+            Ok(syn::Meta::List(l)) => l.nested.into_iter().map(|n| quote! { #n, }).collect(),
+            _ => {
+                return Err(Error::new_spanned(marker, "expected `builder(...)`").to_compile_error())
+            }
+        };
+
+        let receiver = match method.sig.receiver() {
+            Some(FnArg::Receiver(r)) if r.reference.is_some() => r.clone(),
+            _ => {
+                return Err(Error::new_spanned(
+                    &method.sig,
+                    "`#[builder]` methods have to take `&self` or `&mut self`",
+                )
+                .to_compile_error())
+            }
+        };
+
+        let mut fixer = TypeFixer {
+            lifetime: Lifetime::new(BUILDER_LIFETIME, Span::call_site()),
+            self_ty: Some(&item.self_ty),
+            used: true,
+        };
+
+        let fn_args = get_args(&mut method.sig, &mut fixer)?;
+        let fields = get_target_fields(&fn_args)?;
+        let container = get_container_attrs(method_args, &fields)?;
+
+        if container.env_prefix.is_some() || fields.iter().any(|f| f.attrs.env.is_some()) {
+            return Err(Error::new_spanned(
+                &method.sig.ident,
+                "`from_env` is not supported on methods",
+            )
+            .to_compile_error());
+        }
+
+        let finish_output = get_output(&method.sig, &mut fixer);
+        let generics = get_builder_generics(&fixer, &[&item.generics, &method.sig.generics]);
+        let start_generics = get_builder_generics(&fixer, &[&method.sig.generics]);
+
+        let name = method.sig.ident.clone();
+        let hidden = format_ident!("__builder_{}", name);
+        let base_ident = format_ident!("{}{}", self_name, camel_case(&name));
+        let builder_ident = crate::create_builder_ident(&base_ident);
+        let idents = fields.iter().map(|f| f.ident.clone()).collect::<Vec<_>>();
+        let turbofish = get_turbofish(&method.sig.generics);
+        let asyncness = &method.sig.asyncness;
+        let unsafety = &method.sig.unsafety;
+        let awaiting = asyncness.map(|_| quote! { .await });
+        let self_ty = &item.self_ty;
+        let lifetime = &fixer.lifetime;
+        let mutability = &receiver.mutability;
+
+        let builder = expand_builder(&BuilderSpec {
+            base_ident,
+            vis: &method.vis,
+            generics: generics.clone(),
+            container,
+            fields,
+            extra_fields: vec![(
+                format_ident!("__receiver"),
+                quote! { &#lifetime #mutability #self_ty },
+            )],
+            finish: quote! { #asyncness #unsafety fn call },
+            finish_output,
+            finish_expr: quote! {
+                #unsafety {
+                    self.__receiver.#hidden #turbofish (#(#idents),*) #awaiting
+                }
+            },
+        })?;
+
+        builders.push(builder);
+
+        let (_, ty_generics, _) = generics.split_for_impl();
+        let where_clause = &start_generics.where_clause;
+        let attrs = &method.attrs;
+        let vis = &method.vis;
+
+        items.push(syn::parse_quote! {
+            #(#attrs)*
+            #vis fn #name #start_generics (&#lifetime #mutability self)
+                -> #builder_ident #ty_generics #where_clause
+            {
+                #builder_ident::new(self)
+            }
+        });
+
+        method
+            .attrs
+            .retain(|a| a.path.is_ident("allow") || a.path.is_ident("cfg"));
+        method.attrs.push(syn::parse_quote!(#[doc(hidden)]));
+        method.vis = syn::Visibility::Inherited;
+        method.sig.ident = hidden;
+        items.push(ImplItem::Method(method));
+    }
+
+    item.items = items;
+
+    Ok(quote! {
+        #item
+        #(#builders)*
+    })
+}
+
+pub fn expand(args: TokenStream, input: TokenStream) -> TokenStream {
+    use syn::Item;
+
+    let result = match syn::parse2::<Item>(input) {
+        Ok(Item::Fn(f)) => expand_fn(args, f),
+        Ok(Item::Impl(i)) => expand_impl(args, i),
+        Ok(other) => Err(Error::new_spanned(
+            other,
+            "`#[builder]` can be applied only to functions and impl blocks",
+        )
+        .to_compile_error()),
+        Err(e) => Err(e.to_compile_error()),
+    };
+
+    result.unwrap_or_else(|e| e)
+}
@@ -1,8 +1,11 @@
-#![feature(proc_macro_span)]
-
 use proc_macro::TokenStream;
-use proc_macro2::{TokenStream as TokenStream2, Ident};
-use syn::{DataStruct, DeriveInput, Field, Fields, Type};
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use syn::{DataStruct, DeriveInput, Fields, Type};
+
+mod attrs;
+mod func;
+
+use attrs::{ContainerAttrs, FieldAttrs, FieldGroup};
 
 // Get syn::DataStruct out of syn::Data..
 // If Builder macro was applied to Union or Struct then returns TokenStream with error.
@@ -39,6 +42,38 @@ fn get_struct_data(derive_input: &DeriveInput) -> Result<&DataStruct, TokenStrea
     .into())
 }
 
+macro_rules! get_or_none {
+    ($t:expr, $($arm:tt)+) => {
+        match $t {
+            $($arm)*,
+            _ => return None,
+        }
+    }
+}
+
+// Get type wrapped by `outter`, for example `T` out of `Option<T>` when `outter` is "Option".
+fn get_inner_type<'a>(ty: &'a Type, outter: &'static str) -> Option<&'a Type> {
+    use syn::{GenericArgument, PathArguments};
+
+    let path = get_or_none!(ty, Type::Path(p) => p);
+    let first = path.path.segments.iter().next();
+    let seg = get_or_none!(first, Some(s) => s);
+
+    if seg.ident != outter {
+        return None;
+    }
+
+    let type_arg = get_or_none!(seg.arguments, PathArguments::AngleBracketed(ref i) => i);
+
+    if type_arg.args.len() > 1 {
+        return None;
+    }
+
+    let inner = get_or_none!(type_arg.args.iter().next(), Some(p) => p);
+
+    Some(get_or_none!(inner, GenericArgument::Type(ref ty) => ty))
+}
+
 fn create_builder_ident(target_ident: &Ident) -> Ident {
     Ident::new(
         &format!("{}Builder", target_ident),
@@ -46,234 +81,918 @@ fn create_builder_ident(target_ident: &Ident) -> Ident {
     )
 }
 
-// Get list of struct fields.
-// If Builder macro was applied to unit or tuple struct then returns TokenStream with error.
-fn get_target_fields<'a>(
-    target_ident: &'a Ident,
-    fields: &'a Fields,
-) -> Result<Vec<&'a Field>, TokenStream> {
-    use quote::quote_spanned;
+fn create_builder_error_ident(target_ident: &Ident) -> Ident {
+    Ident::new(
+        &format!("{}BuilderError", target_ident),
+        proc_macro2::Span::call_site(),
+    )
+}
 
-    match fields {
-        Fields::Named(f) => Ok(f.named.iter().collect::<Vec<_>>()),
-        _ => Err(quote_spanned! {
-            target_ident.span() => compile_error!("Builder macro supports only structs.")
-        }
-        .into()),
+fn create_set_error_ident(target_ident: &Ident) -> Ident {
+    Ident::new(
+        &format!("{}BuilderSetError", target_ident),
+        proc_macro2::Span::call_site(),
+    )
+}
+
+fn create_env_error_ident(target_ident: &Ident) -> Ident {
+    Ident::new(
+        &format!("{}BuilderEnvError", target_ident),
+        proc_macro2::Span::call_site(),
+    )
+}
+
+// Named struct field together with options given in its #[builder(...)] attributes.
+struct TargetField<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    attrs: FieldAttrs,
+}
+
+impl TargetField<'_> {
+    // Type of the value collected by the builder, which may differ from the type of the field.
+    fn builder_ty(&self) -> &Type {
+        self.attrs.field_ty.as_ref().unwrap_or(self.ty)
     }
 }
 
-// Get list of builder fields.
-//
-// Returns (*builder fields definition*, *builder fields new*, *builder_methods*, *builder build*).
-//
-// struct Builder {
-//     {builder_field_name: builder_field_type}
-//     ..
-// }
+// Get expression checking whether builder field was set.
+fn field_is_set(field: &TargetField) -> TokenStream2 {
+    use quote::quote;
+
+    let ident = field.ident;
+
+    if get_inner_type(field.builder_ty(), "Option").is_none()
+        && get_inner_type(field.builder_ty(), "Vec").is_some()
+    {
+        quote! { !self.#ident.is_empty() }
+    } else {
+        quote! { self.#ident.is_some() }
+    }
+}
+
+// Get methods exposing state of the builder fields.
 //
 // impl Builder {
-//     fn new() -> Self {
-//         Self {
-//             {builder_field_name: builder_field_type}
-//             ..
-//         }
-//     }
-//
-//     fn {builder_field_name} (&mut self, val: {builder_method_type}) -> &mut Self;
-//
-//     fn build(&mut self) -> {target} {
-//         Self {
-//             {builder_field_name: builder_field_build}
-//             ..
-//         }
-//     }
+//     fn {field}_ref(&self) -> Option<&{field_type}>;
+//     fn is_{field}_set(&self) -> bool;
+//     fn clear_{field}(&mut self);
 //     ..
 // }
-fn get_builder_fields(
-    target_fields: &[&Field],
-) -> (
-    Vec<TokenStream2>,
-    Vec<TokenStream2>,
-    Vec<TokenStream2>,
-    Vec<TokenStream2>,
-) {
+fn get_builder_introspection(target_fields: &[TargetField], is_const: bool) -> Vec<TokenStream2> {
+    use quote::{format_ident, quote};
+    use syn::ext::IdentExt;
+
+    let constness = if is_const {
+        quote! { const }
+    } else {
+        quote! {}
+    };
+
+    target_fields
+        .iter()
+        .map(|f| {
+            let ident = f.ident;
+            let name = ident.unraw();
+            let ref_ident = format_ident!("{}_ref", name);
+            let is_set_ident = format_ident!("is_{}_set", name);
+            let clear_ident = format_ident!("clear_{}", name);
+            let is_set = field_is_set(f);
+            let t_opt = quote! { ::std::option::Option };
+
+            let (ref_ty, ref_body, clear_body) = match (
+                get_inner_type(f.builder_ty(), "Option"),
+                get_inner_type(f.builder_ty(), "Vec"),
+            ) {
+                (None, Some(_)) => (
+                    f.builder_ty(),
+                    quote! {
+                        if self.#ident.is_empty() {
+                            #t_opt::None
+                        } else {
+                            #t_opt::Some(&self.#ident)
+                        }
+                    },
+                    quote! { self.#ident.clear(); },
+                ),
+                (inner, _) => (
+                    inner.unwrap_or(f.builder_ty()),
+                    quote! { self.#ident.as_ref() },
+                    quote! { self.#ident = #t_opt::None; },
+                ),
+            };
+
+            quote! {
+                #constness fn #ref_ident(&self) -> #t_opt<&#ref_ty> {
+                    #ref_body
+                }
+
+                #constness fn #is_set_ident(&self) -> bool {
+                    #is_set
+                }
+
+                fn #clear_ident(&mut self) {
+                    #clear_body
+                }
+            }
+        })
+        .collect()
+}
+
+// Get checks of field groups executed at the beginning of `build` method.
+// `bail` is executed before returning the error, const builders use it to forget `self`.
+fn get_field_group_checks(
+    groups: &[FieldGroup],
+    target_fields: &[TargetField],
+    error_ident: &Ident,
+    bail: &TokenStream2,
+) -> Vec<TokenStream2> {
     use quote::quote;
-    use syn::Attribute;
 
-    macro_rules! get_or_none {
-        ($t:expr, $($arm:tt)+) => {
-            match $t {
-                $($arm)*,
-                _ => return None,
+    let is_set = |ident: &Ident| {
+        let field = target_fields
+            .iter()
+            .find(|f| f.ident == ident)
+            .expect("Field groups refer to existing fields.");
+
+        field_is_set(field)
+    };
+
+    groups
+        .iter()
+        .map(|group| match group {
+            FieldGroup::Exclusive(idents) => {
+                let names = idents.iter().map(|i| i.to_string());
+                let set = idents.iter().map(is_set);
+
+                quote! {
+                    if 0 #(+ (#set as usize))* > 1 {
+                        #bail
+                        return ::std::result::Result::Err(
+                            #error_ident::ExclusiveFields(&[#(#names),*])
+                        );
+                    }
+                }
+            }
+            FieldGroup::RequireOneOf(idents) => {
+                let names = idents.iter().map(|i| i.to_string());
+                let set = idents.iter().map(is_set);
+
+                quote! {
+                    if !(#(#set)||*) {
+                        #bail
+                        return ::std::result::Result::Err(
+                            #error_ident::RequireOneOf(&[#(#names),*])
+                        );
+                    }
+                }
             }
+        })
+        .collect()
+}
+
+// Get definition of error returned by the `build` method.
+// The InvalidField variant is present only if some field is converted with `try_build`,
+// so that the error of const builder can be dropped at compile time.
+fn get_builder_error(
+    target_vis: &syn::Visibility,
+    error_ident: &Ident,
+    has_invalid: bool,
+) -> TokenStream2 {
+    use quote::quote;
+
+    let (invalid_def, invalid_fmt) = if has_invalid {
+        (
+            quote! {
+                InvalidField {
+                    field: &'static str,
+                    message: ::std::string::String,
+                },
+            },
+            quote! {
+                Self::InvalidField { field, message } => {
+                    return ::std::write!(f, "invalid value of field `{}`: {}", field, message);
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    quote! {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #target_vis enum #error_ident {
+            MissingField(&'static str),
+            ExclusiveFields(&'static [&'static str]),
+            RequireOneOf(&'static [&'static str]),
+            #invalid_def
         }
-    }
 
-    fn get_inner_type<'a>(ty: &'a Type, outter: &'static str) -> Option<&'a Type> {
-        use syn::{GenericArgument, PathArguments};
+        impl ::std::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let (msg, fields): (&str, &[&str]) = match self {
+                    Self::MissingField(field) => {
+                        return ::std::write!(f, "field `{}` is not set", field);
+                    }
+                    #invalid_fmt
+                    Self::ExclusiveFields(fields) => ("at most one of fields can be set:", fields),
+                    Self::RequireOneOf(fields) => ("at least one of fields must be set:", fields),
+                };
 
-        let path = get_or_none!(ty, Type::Path(p) => p);
-        let first = path.path.segments.iter().next();
-        let seg = get_or_none!(first, Some(s) => s);
+                f.write_str(msg)?;
 
-        if seg.ident != outter {
-            return None;
+                for (i, field) in fields.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    ::std::write!(f, "{}`{}`", sep, field)?;
+                }
+
+                ::std::result::Result::Ok(())
+            }
         }
 
-        let type_arg = get_or_none!(seg.arguments, PathArguments::AngleBracketed(ref i) => i);
+        impl ::std::error::Error for #error_ident {}
+    }
+}
+
+// Get value of the field out of the value collected by the builder.
+// Applies conversion given with #[builder(field(build = "..."))] or #[builder(field(try_build = "..."))].
+fn get_field_conversion(
+    field: &TargetField,
+    value: TokenStream2,
+    error_ident: &Ident,
+) -> TokenStream2 {
+    use attrs::FieldBuild;
+    use quote::quote;
+    use syn::ext::IdentExt;
+
+    let ident = field.ident;
+    let name = ident.unraw().to_string();
 
-        if type_arg.args.len() > 1 {
-            return None;
+    match field.attrs.field_build {
+        None => value,
+        Some(FieldBuild::Value(ref expr)) => quote! {
+            {
+                let #ident = #value;
+                #expr
+            }
+        },
+        Some(FieldBuild::Try(ref expr)) => quote! {
+            match {
+                let #ident = #value;
+                #expr
+            } {
+                ::std::result::Result::Ok(v) => v,
+                ::std::result::Result::Err(e) => return ::std::result::Result::Err(
+                    #error_ident::InvalidField {
+                        field: #name,
+                        message: ::std::string::ToString::to_string(&e),
+                    }
+                ),
+            }
+        },
+    }
+}
+
+// Get indices of the fields which values are used by the `default_with` expression.
+// A single-segment path naming a field is a use of its value, unless the name is shadowed by
+// a closure parameter or a local binding of the expression.
+fn get_default_deps(expr: &syn::Expr, fields: &[TargetField]) -> Vec<usize> {
+    use syn::visit::{self, Visit};
+
+    struct Deps<'a> {
+        fields: &'a [TargetField<'a>],
+        // Names bound inside the expression, one entry per nested scope.
+        scopes: Vec<Vec<Ident>>,
+        deps: Vec<usize>,
+    }
+
+    // Names bound by a pattern.
+    fn bindings(pat: &syn::Pat) -> Vec<Ident> {
+        struct Bindings(Vec<Ident>);
+
+        impl<'ast> Visit<'ast> for Bindings {
+            fn visit_pat_ident(&mut self, i: &'ast syn::PatIdent) {
+                self.0.push(i.ident.clone());
+                visit::visit_pat_ident(self, i);
+            }
         }
 
-        let inner = get_or_none!(type_arg.args.iter().next(), Some(p) => p);
+        let mut b = Bindings(Vec::new());
+        b.visit_pat(pat);
+        b.0
+    }
+
+    impl Deps<'_> {
+        fn use_name(&mut self, ident: &Ident) {
+            if self.scopes.iter().flatten().any(|b| b == ident) {
+                return;
+            }
 
-        Some(get_or_none!(inner, GenericArgument::Type(ref ty) => ty))
+            if let Some(at) = self.fields.iter().position(|f| f.ident == ident) {
+                self.deps.push(at);
+            }
+        }
+
+        // Visit `f` with the names bound by `pats` in scope.
+        fn scoped<'p>(
+            &mut self,
+            pats: impl IntoIterator<Item = &'p syn::Pat>,
+            f: impl FnOnce(&mut Self),
+        ) {
+            let names = pats.into_iter().flat_map(bindings).collect();
+            self.scopes.push(names);
+            f(self);
+            self.scopes.pop();
+        }
     }
 
-    fn get_each_attr(attrs: &Vec<Attribute>) -> Result<Option<Ident>, TokenStream2> {
-        use syn::{parse, Lit, Meta, NestedMeta};
+    impl<'ast> Visit<'ast> for Deps<'_> {
+        fn visit_expr_path(&mut self, e: &'ast syn::ExprPath) {
+            if let (None, Some(ident)) = (&e.qself, e.path.get_ident()) {
+                self.use_name(ident);
+            }
+
+            visit::visit_expr_path(self, e);
+        }
+
+        fn visit_expr_closure(&mut self, e: &'ast syn::ExprClosure) {
+            self.scoped(&e.inputs, |v| v.visit_expr(&e.body));
+        }
+
+        fn visit_block(&mut self, b: &'ast syn::Block) {
+            self.scopes.push(Vec::new());
+
+            for stmt in &b.stmts {
+                match stmt {
+                    syn::Stmt::Local(local) => {
+                        if let Some((_, ref init)) = local.init {
+                            self.visit_expr(init);
+                        }
 
-        for attr in attrs {
-            if !attr.path.is_ident("builder") {
-                continue;
+                        let names = bindings(&local.pat);
+                        self.scopes.last_mut().unwrap().extend(names);
+                    }
+                    _ => self.visit_stmt(stmt),
+                }
             }
 
-            let meta = attr.parse_meta();
+            self.scopes.pop();
+        }
 
-            if meta.is_err() {
-                return Err(parse::Error::new_spanned(
-                    attr,
-                    "Unrecognized argument to builder attribute",
-                )
-                .to_compile_error());
+        fn visit_arm(&mut self, a: &'ast syn::Arm) {
+            self.scoped([&a.pat], |v| {
+                if let Some((_, ref guard)) = a.guard {
+                    v.visit_expr(guard);
+                }
+
+                v.visit_expr(&a.body);
+            });
+        }
+
+        fn visit_expr_for_loop(&mut self, e: &'ast syn::ExprForLoop) {
+            self.visit_expr(&e.expr);
+            self.scoped([&e.pat], |v| v.visit_block(&e.body));
+        }
+
+        fn visit_expr_if(&mut self, e: &'ast syn::ExprIf) {
+            match *e.cond {
+                syn::Expr::Let(ref l) => {
+                    self.visit_expr(&l.expr);
+                    self.scoped([&l.pat], |v| v.visit_block(&e.then_branch));
+                }
+                ref cond => {
+                    self.visit_expr(cond);
+                    self.visit_block(&e.then_branch);
+                }
             }
 
-            let meta = meta.unwrap();
+            if let Some((_, ref else_branch)) = e.else_branch {
+                self.visit_expr(else_branch);
+            }
+        }
 
-            let list = match meta {
-                Meta::List(n) => n,
-                _ => {
-                    return Err(parse::Error::new_spanned(
-                        meta,
-                        "Unrecognized argument to builder attribute",
-                    )
-                    .to_compile_error())
+        fn visit_expr_while(&mut self, e: &'ast syn::ExprWhile) {
+            match *e.cond {
+                syn::Expr::Let(ref l) => {
+                    self.visit_expr(&l.expr);
+                    self.scoped([&l.pat], |v| v.visit_block(&e.body));
                 }
-            };
+                ref cond => {
+                    self.visit_expr(cond);
+                    self.visit_block(&e.body);
+                }
+            }
+        }
+
+        // Arguments of macros like `format!` are expressions separated by commas.
+        fn visit_macro(&mut self, m: &'ast syn::Macro) {
+            use syn::parse::Parser;
+            use syn::punctuated::Punctuated;
+
+            let parser = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+
+            if let Ok(args) = parser.parse2(m.tokens.clone()) {
+                for arg in &args {
+                    self.visit_expr(arg);
+                }
+            }
+        }
+    }
+
+    let mut visitor = Deps {
+        fields,
+        scopes: Vec::new(),
+        deps: Vec::new(),
+    };
+    visitor.visit_expr(expr);
 
-            if list.nested.len() != 1 {
-                return Err(parse::Error::new_spanned(
-                    list,
-                    "Unrecognized argument to builder attribute",
+    visitor.deps
+}
+
+// Get order in which values of the fields are resolved by the finishing method.
+// Fields with `default_with` are resolved after the fields they use.
+// Returns TokenStream with error if the defaults depend on each other in a cycle.
+fn get_resolve_order(fields: &[TargetField]) -> Result<Vec<usize>, TokenStream2> {
+    use syn::ext::IdentExt;
+
+    let deps = fields
+        .iter()
+        .map(|f| match f.attrs.default_with {
+            Some((_, ref expr)) => get_default_deps(expr, fields),
+            None => Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut order: Vec<usize> = Vec::new();
+
+    while order.len() < fields.len() {
+        let next = (0..fields.len())
+            .find(|i| !order.contains(i) && deps[*i].iter().all(|d| order.contains(d)));
+
+        match next {
+            Some(i) => order.push(i),
+            None => {
+                // Every field left depends on another field left, so following the
+                // dependencies from any of them ends up going around a cycle.
+                let mut path = vec![(0..fields.len()).find(|i| !order.contains(i)).unwrap()];
+                let start = loop {
+                    let last = *path.last().unwrap();
+                    let dep = *deps[last].iter().find(|d| !order.contains(d)).unwrap();
+
+                    match path.iter().position(|p| *p == dep) {
+                        Some(start) => break start,
+                        None => path.push(dep),
+                    }
+                };
+                let mut cycle = path.split_off(start);
+                cycle.sort_unstable();
+                let names = cycle
+                    .iter()
+                    .map(|i| format!("`{}`", fields[*i].ident.unraw()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let (ref lit, _) = fields[cycle[0]]
+                    .attrs
+                    .default_with
+                    .as_ref()
+                    .expect("Fields left unresolved have defaults.");
+
+                return Err(syn::parse::Error::new_spanned(
+                    lit,
+                    format!("cycle in `default_with` of fields {}", names),
                 )
                 .to_compile_error());
             }
+        }
+    }
+
+    Ok(order)
+}
+
+// Get expression parsing `input` with FromStr.
+// On failure returns `error` from the surrounding function, the parse error is available as `e`.
+fn parse_from_str(ty: &Type, input: &TokenStream2, error: TokenStream2) -> TokenStream2 {
+    use quote::quote;
+
+    quote! {
+        match <#ty as ::std::str::FromStr>::from_str(#input) {
+            ::std::result::Result::Ok(v) => v,
+            ::std::result::Result::Err(e) => return ::std::result::Result::Err(#error),
+        }
+    }
+}
+
+// Get `from_env` constructor of the builder.
+// Returns None if neither the struct nor any of its fields refer to environment variables.
+//
+// impl Builder {
+//     fn from_env() -> Result<Self, {target}BuilderEnvError> {
+//         let mut builder = Self::new();
+//         {builder_field_from_env_var}
+//         ..
+//         Ok(builder)
+//     }
+// }
+fn get_builder_from_env(
+    container: &ContainerAttrs,
+    target_fields: &[TargetField],
+    env_error_ident: &Ident,
+) -> Option<TokenStream2> {
+    use quote::quote;
+    use syn::ext::IdentExt;
+
+    if container.env_prefix.is_none() && target_fields.iter().all(|f| f.attrs.env.is_none()) {
+        return None;
+    }
 
-            // Now, we are sure that attribute is something like "#[builder(...)]".
-            let nested_meta = match list.nested.iter().next().unwrap() {
-                NestedMeta::Meta(m) => match m {
-                    Meta::NameValue(v) => v,
-                    un => {
-                        return Err(parse::Error::new_spanned(
-                            un,
-                            "Unrecognized argument to builder attribute",
-                        )
-                        .to_compile_error())
+    let t_res = quote! { ::std::result::Result };
+
+    let loads = target_fields.iter().filter_map(|f| {
+        let ident = f.ident;
+        let var = match (&container.env_prefix, &f.attrs.env) {
+            (Some(prefix), Some(env)) => prefix.value() + &env.value(),
+            (Some(prefix), None) => prefix.value() + &ident.unraw().to_string().to_uppercase(),
+            (None, Some(env)) => env.value(),
+            (None, None) => return None,
+        };
+
+        let parse = |ty: &Type, input: TokenStream2| {
+            parse_from_str(
+                ty,
+                &input,
+                quote! {
+                    #env_error_ident::Parse {
+                        var: #var,
+                        value: ::std::borrow::ToOwned::to_owned(#input),
+                        message: ::std::string::ToString::to_string(&e),
                     }
                 },
-                un => {
-                    return Err(parse::Error::new_spanned(
-                        un,
-                        "Unrecognized argument to builder attribute",
-                    )
-                    .to_compile_error())
+            )
+        };
+
+        let assign = match (
+            get_inner_type(f.builder_ty(), "Option"),
+            get_inner_type(f.builder_ty(), "Vec"),
+        ) {
+            (None, Some(vec_inner)) => {
+                let sep = f
+                    .attrs
+                    .env_separator
+                    .as_ref()
+                    .or(container.env_separator.as_ref())
+                    .map(|s| s.value())
+                    .unwrap_or_else(|| ",".to_owned());
+                let item = parse(vec_inner, quote! { item });
+
+                quote! {
+                    for item in value.split(#sep).filter(|item| !item.is_empty()) {
+                        builder.#ident.push(#item);
+                    }
                 }
-            };
+            }
+            (inner, _) => {
+                let value = parse(inner.unwrap_or(f.builder_ty()), quote! { value.as_str() });
 
-            if !nested_meta.path.is_ident("each") {
-                return Err(parse::Error::new_spanned(
-                    &list,
-                    r#"expected `builder(each = "...")`"#,
-                )
-                .to_compile_error());
+                quote! {
+                    builder.#ident = ::std::option::Option::Some(#value);
+                }
             }
+        };
 
-            let name = match nested_meta.lit {
-                Lit::Str(ref s) => s,
-                _ => {
-                    return Err(parse::Error::new_spanned(
-                        &list,
-                        r#"expected `builder(each = "...")`"#,
-                    )
-                    .to_compile_error())
+        Some(quote! {
+            match ::std::env::var(#var) {
+                #t_res::Ok(value) => {
+                    #assign
                 }
-            };
+                #t_res::Err(::std::env::VarError::NotPresent) => {}
+                #t_res::Err(::std::env::VarError::NotUnicode(_)) => {
+                    return #t_res::Err(#env_error_ident::NotUnicode(#var));
+                }
+            }
+        })
+    });
+
+    Some(quote! {
+        fn from_env() -> #t_res<Self, #env_error_ident> {
+            let mut builder = Self::new();
+
+            #(#loads)*
+
+            #t_res::Ok(builder)
+        }
+    })
+}
+
+// Get definition of error returned by the `from_env` method.
+fn get_env_error(target_vis: &syn::Visibility, env_error_ident: &Ident) -> TokenStream2 {
+    use quote::quote;
+
+    quote! {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #target_vis enum #env_error_ident {
+            NotUnicode(&'static str),
+            Parse {
+                var: &'static str,
+                value: ::std::string::String,
+                message: ::std::string::String,
+            },
+        }
+
+        impl ::std::fmt::Display for #env_error_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    Self::NotUnicode(var) => {
+                        ::std::write!(f, "environment variable `{}` is not valid unicode", var)
+                    }
+                    Self::Parse { var, value, message } => ::std::write!(
+                        f,
+                        "invalid value `{}` of environment variable `{}`: {}",
+                        value,
+                        var,
+                        message
+                    ),
+                }
+            }
+        }
+
+        impl ::std::error::Error for #env_error_ident {}
+    }
+}
+
+// Get `set_by_name` method of the builder.
+// Returns None unless the struct is marked with #[builder(set_by_name)].
+//
+// impl Builder {
+//     fn set_by_name(&mut self, field: &str, value: &str) -> Result<&mut Self, {target}BuilderSetError> {
+//         match field {
+//             "{field}" => self.{field} = Some({value_from_str}),
+//             "{each}" | "{field}" => self.{field}.push({value_from_str}),
+//             ..
+//         }
+//         Ok(self)
+//     }
+// }
+fn get_builder_set_by_name(
+    container: &ContainerAttrs,
+    target_fields: &[TargetField],
+    set_error_ident: &Ident,
+) -> Option<TokenStream2> {
+    use quote::quote;
+    use syn::ext::IdentExt;
+
+    if !container.set_by_name {
+        return None;
+    }
 
-            return Ok(Some(Ident::new(&name.value(), name.span())));
+    let t_res = quote! { ::std::result::Result };
+    let input = quote! { value };
+
+    let arms = target_fields.iter().map(|f| {
+        let ident = f.ident;
+        let name = ident.unraw().to_string();
+        let parse = |ty: &Type| {
+            parse_from_str(
+                ty,
+                &input,
+                quote! {
+                    #set_error_ident::Parse {
+                        field: #name,
+                        value: ::std::borrow::ToOwned::to_owned(value),
+                        message: ::std::string::ToString::to_string(&e),
+                    }
+                },
+            )
+        };
+
+        match (
+            get_inner_type(f.builder_ty(), "Option"),
+            get_inner_type(f.builder_ty(), "Vec"),
+        ) {
+            (None, Some(vec_inner)) => {
+                let value = parse(vec_inner);
+                let each = f
+                    .attrs
+                    .each
+                    .as_ref()
+                    .map(|e| e.unraw().to_string())
+                    .filter(|e| *e != name)
+                    .map(|e| quote! { | #e });
+
+                quote! {
+                    #name #each => self.#ident.push(#value),
+                }
+            }
+            (inner, _) => {
+                let value = parse(inner.unwrap_or(f.builder_ty()));
+
+                quote! {
+                    #name => self.#ident = ::std::option::Option::Some(#value),
+                }
+            }
         }
+    });
+
+    Some(quote! {
+        fn set_by_name(&mut self, field: &str, value: &str) -> #t_res<&mut Self, #set_error_ident> {
+            match field {
+                #(#arms)*
+                _ => return #t_res::Err(
+                    #set_error_ident::UnknownField(::std::borrow::ToOwned::to_owned(field))
+                ),
+            }
 
-        Ok(None)
+            #t_res::Ok(self)
+        }
+    })
+}
+
+// Get definition of error returned by the `set_by_name` method.
+fn get_set_error(target_vis: &syn::Visibility, set_error_ident: &Ident) -> TokenStream2 {
+    use quote::quote;
+
+    quote! {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #target_vis enum #set_error_ident {
+            UnknownField(::std::string::String),
+            Parse {
+                field: &'static str,
+                value: ::std::string::String,
+                message: ::std::string::String,
+            },
+        }
+
+        impl ::std::fmt::Display for #set_error_ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    Self::UnknownField(field) => ::std::write!(f, "unknown field `{}`", field),
+                    Self::Parse { field, value, message } => ::std::write!(
+                        f,
+                        "invalid value `{}` of field `{}`: {}",
+                        value,
+                        field,
+                        message
+                    ),
+                }
+            }
+        }
+
+        impl ::std::error::Error for #set_error_ident {}
     }
+}
+
+// Get list of struct fields.
+// If Builder macro was applied to unit or tuple struct then returns TokenStream with error.
+fn get_target_fields<'a>(
+    target_ident: &'a Ident,
+    fields: &'a Fields,
+) -> Result<Vec<TargetField<'a>>, TokenStream> {
+    use quote::quote_spanned;
+
+    let named = match fields {
+        Fields::Named(f) => &f.named,
+        _ => {
+            return Err(quote_spanned! {
+                target_ident.span() => compile_error!("Builder macro supports only structs.")
+            }
+            .into())
+        }
+    };
+
+    let mut target_fields = Vec::new();
+
+    for f in named {
+        target_fields.push(TargetField {
+            ident: f
+                .ident
+                .as_ref()
+                .expect("Fields should be named at this point."),
+            ty: &f.ty,
+            attrs: attrs::get_field_attrs(&f.attrs)?,
+        });
+    }
+
+    Ok(target_fields)
+}
+
+// Get list of builder fields.
+//
+// Returns (*builder fields definition*, *builder fields new*, *builder_methods*, *builder build*).
+//
+// struct Builder {
+//     {builder_field_name: builder_field_type}
+//     ..
+// }
+//
+// impl Builder {
+//     fn new() -> Self {
+//         Self {
+//             {builder_field_name: builder_field_type}
+//             ..
+//         }
+//     }
+//
+//     fn {builder_field_name} (&mut self, val: {builder_method_type}) -> &mut Self;
+//
+//     fn build(&mut self) -> Result<{target}, {target}BuilderError> {
+//         let {builder_field_name} = {builder_field_build};
+//         ..
+//     }
+//     ..
+// }
+fn get_builder_fields(
+    target_fields: &[TargetField],
+    error_ident: &Ident,
+    is_const: bool,
+) -> (
+    Vec<TokenStream2>,
+    Vec<TokenStream2>,
+    Vec<TokenStream2>,
+    Vec<TokenStream2>,
+) {
+    use quote::quote;
+    use syn::{ext::IdentExt, parse};
+
+    // Setters of const builder take and return the builder by value.
+    let (constness, receiver, output) = if is_const {
+        (quote! { const }, quote! { mut self }, quote! { Self })
+    } else {
+        (quote! {}, quote! { &mut self }, quote! { &mut Self })
+    };
 
     target_fields
         .iter()
         .map(|f| {
-            let ident = f
-                .ident
-                .clone()
-                .expect("Fields should be named at this point.");
+            let ident = f.ident;
             let t_opt = quote! { ::std::option::Option };
+            let vec_inner = get_inner_type(f.builder_ty(), "Vec");
+            let take = if is_const {
+                quote! { self.#ident }
+            } else {
+                quote! { self.#ident.take() }
+            };
+            // The old value can't be dropped in const fn, so it is forgotten instead,
+            // which leaks it if the field is set more than once.
+            let assign = if is_const {
+                quote! {
+                    ::std::mem::forget(::std::mem::replace(&mut self.#ident, #t_opt::Some(value)));
+                }
+            } else {
+                quote! { self.#ident = #t_opt::Some(value); }
+            };
+
+            let each_error = match f.attrs.each {
+                Some(ref each) if vec_inner.is_none() => parse::Error::new_spanned(
+                    each,
+                    "`builder(each = \"...\")` can be used only on Vec fields",
+                )
+                .to_compile_error(),
+                _ => quote! {},
+            };
 
-            if let Some(opt_inner) = get_inner_type(&f.ty, "Option") {
+            if let Some(opt_inner) = get_inner_type(f.builder_ty(), "Option") {
                 return (
                     quote! { #ident: #t_opt<#opt_inner> },
                     quote! { #ident: #t_opt::None },
                     quote! {
-                        fn #ident (&mut self, value: #opt_inner) -> &mut Self {
-                            self.#ident.replace(value);
+                        #each_error
+
+                        #constness fn #ident (#receiver, value: #opt_inner) -> #output {
+                            #assign
                             self
                         }
                     },
-                    quote! { #ident: self.#ident.take() },
+                    take,
                 );
             }
 
-            if let Some(vec_inner) = get_inner_type(&f.ty, "Vec") {
+            if let Some(vec_inner) = vec_inner {
                 let t_vec = quote! { ::std::vec::Vec };
-                let each_attr = get_each_attr(&f.attrs);
-
-                let same_each_as_field = match each_attr {
-                    Ok(Some(ref each_ident)) => *each_ident == ident,
-                    _ => false,
-                };
 
-                let methods = if same_each_as_field {
-                    quote! {
+                let methods = match f.attrs.each {
+                    Some(ref each) if each == ident => quote! {
                         fn #ident (&mut self, value: #vec_inner) -> &mut Self {
                             self.#ident.push(value);
                             self
                         }
-                    }
-                } else {
-                    let each_output = match each_attr {
-                        Err(e) => e,
-                        Ok(None) => quote! {},
-                        Ok(Some(lit)) => quote! {
-                            fn #lit (&mut self, value: #vec_inner) -> &mut Self {
-                                self.#ident.push(value);
-                                self
-                            }
-                        },
-                    };
+                    },
+                    ref each => {
+                        let each_output = match each {
+                            None => quote! {},
+                            Some(each) => quote! {
+                                fn #each (&mut self, value: #vec_inner) -> &mut Self {
+                                    self.#ident.push(value);
+                                    self
+                                }
+                            },
+                        };
 
-                    quote! {
-                        #each_output
+                        quote! {
+                            #each_output
 
-                        fn #ident (&mut self, mut value: #t_vec<#vec_inner>) -> &mut Self {
-                            self.#ident.append(&mut value);
-                            self
+                            fn #ident (&mut self, mut value: #t_vec<#vec_inner>) -> &mut Self {
+                                self.#ident.append(&mut value);
+                                self
+                            }
                         }
                     }
                 };
@@ -283,7 +1002,7 @@ fn get_builder_fields(
                     quote! { #ident: #t_vec::new() },
                     methods,
                     quote! {
-                        #ident: {
+                        {
                             let mut val = #t_vec::new();
                             ::std::mem::swap(&mut self.#ident, &mut val);
                             val
@@ -292,20 +1011,25 @@ fn get_builder_fields(
                 );
             }
 
-            let ty = &f.ty;
+            let ty = f.builder_ty();
+            let name = ident.unraw().to_string();
             (
                 quote! { #ident: #t_opt<#ty> },
                 quote! { #ident: #t_opt::None },
                 quote! {
-                    fn #ident (&mut self, value: #ty) -> &mut Self {
-                        self.#ident.replace(value);
+                    #each_error
+
+                    #constness fn #ident (#receiver, value: #ty) -> #output {
+                        #assign
                         self
                     }
                 },
                 quote! {
-                    #ident: match self.#ident.take() {
+                    match #take {
                         #t_opt::Some(v) => v,
-                        #t_opt::None => return #t_opt::None,
+                        #t_opt::None => return ::std::result::Result::Err(
+                            #error_ident::MissingField(#name)
+                        ),
                     }
                 },
             )
@@ -322,6 +1046,393 @@ fn get_builder_fields(
         )
 }
 
+// The #[bitfield] attribute below the derive replaces the fields of the struct only after
+// the derive has seen them, so the builder would set fields which no longer exist.
+// Returns TokenStream with error pointing to #[bitfield(builder)], which builds the packed value.
+//
+// Only the paths under which the bitfield crate exports the attribute are recognized, other
+// attributes named `bitfield` may come from unrelated crates.
+fn check_not_bitfield(derive_input: &DeriveInput) -> Result<(), TokenStream2> {
+    let bitfield = derive_input.attrs.iter().find(|a| {
+        let path = a
+            .path
+            .segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect::<Vec<_>>();
+
+        path == ["bitfield"] || path == ["bitfield", "bitfield"]
+    });
+
+    match bitfield {
+        Some(attr) => Err(syn::parse::Error::new_spanned(
+            attr,
+            "#[derive(Builder)] can not be used on a #[bitfield] struct, use #[bitfield(builder)] instead",
+        )
+        .to_compile_error()),
+        None => Ok(()),
+    }
+}
+
+// Get statements of the const `build` method binding the values of the fields, after the
+// field group checks.
+//
+// Values with drop glue can't be dropped in const fn, so no path may drop `self` or a value
+// taken out of it. Moving a value out of a field or an `Option` by pattern still leaves its
+// drop to the const checker, so every value is moved as a whole: missing fields are checked
+// while `self` can be forgotten, then the fields are taken out and each `Option` is either
+// unwrapped or forgotten.
+//
+//     if self.{field}.is_none() {
+//         forget(self);
+//         return Err(MissingField("{field}"));
+//     }
+//     ..
+//     let {field} = replace(&mut self.{field}, None);
+//     ..
+//     forget(self);
+//     let {field} = {field}.unwrap();
+//     let {field_with_default} = if {field}.is_some() {
+//         {field}.unwrap()
+//     } else {
+//         forget({field});
+//         {default}
+//     };
+fn get_const_build(fields: &[TargetField], order: &[usize], error_ident: &Ident) -> TokenStream2 {
+    use quote::quote;
+    use syn::ext::IdentExt;
+
+    let t_opt = quote! { ::std::option::Option };
+    let idents = fields.iter().map(|f| f.ident);
+    let required = |f: &TargetField| {
+        f.attrs.default_with.is_none() && get_inner_type(f.builder_ty(), "Option").is_none()
+    };
+
+    let missing_checks = order
+        .iter()
+        .map(|i| &fields[*i])
+        .filter(|f| required(f))
+        .map(|f| {
+            let ident = f.ident;
+            let name = ident.unraw().to_string();
+
+            quote! {
+                if self.#ident.is_none() {
+                    ::std::mem::forget(self);
+                    return ::std::result::Result::Err(#error_ident::MissingField(#name));
+                }
+            }
+        });
+
+    let values = order.iter().map(|i| &fields[*i]).map(|f| {
+        let ident = f.ident;
+        let is_option = get_inner_type(f.builder_ty(), "Option").is_some();
+        // The builder stores an optional field as the field type itself.
+        let set = if is_option {
+            quote! { #ident }
+        } else {
+            quote! { #ident.unwrap() }
+        };
+        let set = get_field_conversion(f, set, error_ident);
+
+        match f.attrs.default_with {
+            Some((_, ref default)) => quote! {
+                let #ident = if #ident.is_some() {
+                    #set
+                } else {
+                    ::std::mem::forget(#ident);
+                    #default
+                };
+            },
+            None => quote! { let #ident = #set; },
+        }
+    });
+
+    quote! {
+        #(#missing_checks)*
+
+        #(let #idents = ::std::mem::replace(&mut self.#idents, #t_opt::None);)*
+        ::std::mem::forget(self);
+
+        #(#values)*
+    }
+}
+
+// Builder generated for a struct or a function.
+struct BuilderSpec<'a> {
+    // Name of the item the builder produces, the builder and its errors are named after it.
+    base_ident: Ident,
+    vis: &'a syn::Visibility,
+    generics: syn::Generics,
+    container: ContainerAttrs,
+    fields: Vec<TargetField<'a>>,
+    // Builder fields without setters, `new` takes their values as arguments.
+    extra_fields: Vec<(Ident, TokenStream2)>,
+    // Start of the method signature producing the output, for example `fn build`.
+    finish: TokenStream2,
+    finish_output: TokenStream2,
+    // Expression producing the output out of the `let` bound field values.
+    finish_expr: TokenStream2,
+}
+
+// Check that the methods generated for the fields don't clash with each other
+// or with the other methods of the builder. A field named `reset` or `a_ref`
+// next to a field `a` would otherwise produce duplicate definitions.
+// Returns TokenStream with error spanned on the field introducing the clash.
+fn check_method_names(spec: &BuilderSpec) -> Result<(), TokenStream2> {
+    use quote::format_ident;
+    use std::collections::HashMap;
+    use syn::ext::IdentExt;
+
+    let BuilderSpec {
+        container,
+        fields,
+        finish,
+        ..
+    } = spec;
+
+    let finish_ident = finish.clone().into_iter().filter_map(|t| match t {
+        proc_macro2::TokenTree::Ident(i) => Some(i),
+        _ => None,
+    });
+    let mut builder_methods = vec![format_ident!("new"), format_ident!("reset")];
+    builder_methods.extend(finish_ident.last());
+
+    if container.env_prefix.is_some() || fields.iter().any(|f| f.attrs.env.is_some()) {
+        builder_methods.push(format_ident!("from_env"));
+    }
+
+    if container.set_by_name {
+        builder_methods.push(format_ident!("set_by_name"));
+    }
+
+    let mut defined: HashMap<String, Option<&Ident>> = builder_methods
+        .iter()
+        .map(|m| (m.to_string(), None))
+        .collect();
+
+    for f in fields {
+        let name = f.ident.unraw();
+        let mut methods = vec![
+            name.clone(),
+            format_ident!("{}_ref", name),
+            format_ident!("is_{}_set", name),
+            format_ident!("clear_{}", name),
+        ];
+        methods.extend(
+            f.attrs
+                .each
+                .as_ref()
+                .map(|e| e.unraw())
+                .filter(|e| *e != name),
+        );
+
+        for method in methods {
+            let owner = match defined.insert(method.to_string(), Some(f.ident)) {
+                None => continue,
+                Some(None) => "the builder".to_owned(),
+                Some(Some(other)) => format!("field `{}`", other.unraw()),
+            };
+
+            return Err(syn::parse::Error::new_spanned(
+                f.ident,
+                format!(
+                    "builder method `{}` of this field is already generated for {}, rename the field",
+                    method, owner
+                ),
+            )
+            .to_compile_error());
+        }
+    }
+
+    Ok(())
+}
+
+// Get builder struct, its impl block and error types.
+//
+// impl Builder {
+//     fn new({extra_fields}) -> Self;
+//     {setters, introspection, from_env, set_by_name}
+//     fn reset(&mut self);
+//
+//     {finish}(&mut self) -> Result<{finish_output}, {base}BuilderError> {
+//         {field_group_checks}
+//         let {builder_field_name} = {builder_field_build};
+//         ..
+//         Ok({finish_expr})
+//     }
+// }
+fn expand_builder(spec: &BuilderSpec) -> Result<TokenStream2, TokenStream2> {
+    use quote::{format_ident, quote};
+    use syn::ext::IdentExt;
+
+    let BuilderSpec {
+        base_ident,
+        vis,
+        generics,
+        container,
+        fields,
+        extra_fields,
+        finish,
+        finish_output,
+        finish_expr,
+    } = spec;
+
+    check_method_names(spec)?;
+
+    let has_invalid = fields
+        .iter()
+        .find(|f| matches!(f.attrs.field_build, Some(attrs::FieldBuild::Try(_))));
+
+    if let (true, Some(f)) = (container.is_const, has_invalid) {
+        return Err(syn::parse::Error::new_spanned(
+            f.ident,
+            "`builder(field(try_build = \"...\"))` is not supported by `builder(const)`",
+        )
+        .to_compile_error());
+    }
+
+    if container.is_const {
+        let vec_field = fields.iter().find(|f| {
+            get_inner_type(f.builder_ty(), "Option").is_none()
+                && get_inner_type(f.builder_ty(), "Vec").is_some()
+        });
+
+        if let Some(f) = vec_field {
+            return Err(syn::parse::Error::new_spanned(
+                f.builder_ty(),
+                "Vec fields are not supported by `builder(const)`, use an array or a slice instead",
+            )
+            .to_compile_error());
+        }
+    }
+
+    let builder_ident = create_builder_ident(base_ident);
+    let error_ident = create_builder_error_ident(base_ident);
+    let (builder_def, builder_new, builder_met, builder_build) =
+        get_builder_fields(fields, &error_ident, container.is_const);
+    let builder_introspection = get_builder_introspection(fields, container.is_const);
+    let bail = if container.is_const {
+        quote! { ::std::mem::forget(self); }
+    } else {
+        quote! {}
+    };
+    let builder_checks = get_field_group_checks(&container.groups, fields, &error_ident, &bail);
+    let builder_error = get_builder_error(vis, &error_ident, has_invalid.is_some());
+    let builder_build = fields
+        .iter()
+        .zip(builder_build)
+        .map(|(f, value)| {
+            let value = get_field_conversion(f, value, &error_ident);
+
+            match f.attrs.default_with {
+                Some((_, ref default)) => {
+                    let is_set = field_is_set(f);
+                    quote! { if #is_set { #value } else { #default } }
+                }
+                None => value,
+            }
+        })
+        .collect::<Vec<_>>();
+    let order = get_resolve_order(fields)?;
+    let resolved_idents = order.iter().map(|i| fields[*i].ident);
+    let resolved_build = order.iter().map(|i| &builder_build[*i]);
+    let finish_body = if container.is_const {
+        get_const_build(fields, &order, &error_ident)
+    } else {
+        quote! { #(let #resolved_idents = #resolved_build;)* }
+    };
+    let env_error_ident = create_env_error_ident(base_ident);
+    let builder_from_env = get_builder_from_env(container, fields, &env_error_ident);
+    let env_error = builder_from_env
+        .as_ref()
+        .map(|_| get_env_error(vis, &env_error_ident));
+    let set_error_ident = create_set_error_ident(base_ident);
+    let builder_set_by_name = get_builder_set_by_name(container, fields, &set_error_ident);
+    let set_error = builder_set_by_name
+        .as_ref()
+        .map(|_| get_set_error(vis, &set_error_ident));
+
+    let idents = fields.iter().map(|f| f.ident).collect::<Vec<_>>();
+    let clear_idents = idents.iter().map(|i| format_ident!("clear_{}", i.unraw()));
+    let (extra_idents, extra_tys): (Vec<_>, Vec<_>) = extra_fields.iter().cloned().unzip();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Generic parameters may appear only in the output, mark them as used.
+    let marker_params = generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            syn::GenericParam::Lifetime(l) => {
+                let lifetime = &l.lifetime;
+                Some(quote! { &#lifetime () })
+            }
+            syn::GenericParam::Type(t) => {
+                let ident = &t.ident;
+                Some(quote! { #ident })
+            }
+            syn::GenericParam::Const(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let (marker_def, marker_new) = if marker_params.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! { __marker: ::std::marker::PhantomData<fn() -> (#(#marker_params,)*)>, },
+            quote! { __marker: ::std::marker::PhantomData, },
+        )
+    };
+
+    // Const builder is consumed by the finishing method.
+    let (constness, finish_receiver) = if container.is_const {
+        (quote! { const }, quote! { mut self })
+    } else {
+        (quote! {}, quote! { &mut self })
+    };
+
+    Ok(quote! {
+        #vis struct #builder_ident #generics #where_clause {
+            #(#extra_idents: #extra_tys,)*
+            #marker_def
+            #(#builder_def,)*
+        }
+
+        impl #impl_generics #builder_ident #ty_generics #where_clause {
+            #constness fn new(#(#extra_idents: #extra_tys),*) -> Self {
+                Self {
+                    #(#extra_idents,)*
+                    #marker_new
+                    #(#builder_new,)*
+                }
+            }
+
+            #(#builder_met)*
+
+            #(#builder_introspection)*
+
+            fn reset(&mut self) {
+                #(self.#clear_idents();)*
+            }
+
+            #builder_from_env
+
+            #builder_set_by_name
+
+            #constness #finish(#finish_receiver) -> ::std::result::Result<#finish_output, #error_ident> {
+                #(#builder_checks)*
+
+                #finish_body
+
+                ::std::result::Result::Ok(#finish_expr)
+            }
+        }
+
+        #builder_error
+        #env_error
+        #set_error
+    })
+}
+
 #[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: TokenStream) -> TokenStream {
     use quote::quote;
@@ -333,6 +1444,10 @@ pub fn derive(input: TokenStream) -> TokenStream {
         Err(e) => return e,
     };
 
+    if let Err(e) = check_not_bitfield(&derive_input) {
+        return e.into();
+    }
+
     let DeriveInput {
         ident: target_ident,
         vis: target_vis,
@@ -344,39 +1459,40 @@ pub fn derive(input: TokenStream) -> TokenStream {
         Err(e) => return e,
     };
 
-    let builder_ident = create_builder_ident(target_ident);
-    let (builder_def, builder_new, builder_met, builder_build) = get_builder_fields(&target_fields);
-
-    // Struct builder definition.
-    let builder_def = quote! {
-        #target_vis struct #builder_ident {
-            #(#builder_def,)*
-        }
+    let field_idents = target_fields.iter().map(|f| f.ident).collect::<Vec<_>>();
+    let container = match attrs::get_container_attrs(&derive_input.attrs, &field_idents) {
+        Ok(c) => c,
+        Err(e) => return e.into(),
     };
 
-    // Struct builder impl block.
-    let builder_impl = quote! {
-        impl #builder_ident {
-            fn new() -> Self {
-                Self {
-                    #(#builder_new,)*
-                }
-            }
+    let builder_ident = create_builder_ident(target_ident);
+    let constness = if container.is_const {
+        quote! { const }
+    } else {
+        quote! {}
+    };
 
-            #(#builder_met)*
+    let builder = expand_builder(&BuilderSpec {
+        base_ident: target_ident.clone(),
+        vis: target_vis,
+        generics: Default::default(),
+        container,
+        fields: target_fields,
+        extra_fields: Vec::new(),
+        finish: quote! { fn build },
+        finish_output: quote! { #target_ident },
+        finish_expr: quote! { #target_ident { #(#field_idents,)* } },
+    });
 
-            fn build(&mut self) -> ::std::option::Option<#target_ident> {
-                Some(#target_ident {
-                    #(#builder_build,)*
-                })
-            }
-        }
+    let builder = match builder {
+        Ok(b) => b,
+        Err(e) => return e.into(),
     };
 
     // The `build` method implementation on marked struct.
     let target_bulid_impl = quote! {
         impl #target_ident {
-            #target_vis fn builder() -> #builder_ident {
+            #target_vis #constness fn builder() -> #builder_ident {
                 #builder_ident::new()
             }
         }
@@ -384,8 +1500,12 @@ pub fn derive(input: TokenStream) -> TokenStream {
 
     quote! {
         #target_bulid_impl
-        #builder_def
-        #builder_impl
+        #builder
     }
     .into()
 }
+
+#[proc_macro_attribute]
+pub fn builder(args: TokenStream, input: TokenStream) -> TokenStream {
+    func::expand(args.into(), input.into()).into()
+}
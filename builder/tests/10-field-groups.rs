@@ -0,0 +1,88 @@
+// Some APIs accept one of several alternative sets of arguments, for example
+// either `url` or `host` but never both of them at once.
+//
+// Look for container attributes #[builder(exclusive(...))] and
+// #[builder(require_one_of(...))] listing names of the fields. The `build`
+// method should check these constraints before the target struct is created
+// and report violation through the error value.
+//
+//     impl ConnectionBuilder {
+//         fn build(&mut self) -> Result<Connection, ConnectionBuilderError> {
+//             ...
+//         }
+//     }
+//
+// A field stored as Option counts as set once its setter was called, a Vec
+// field once it holds at least one element.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(exclusive(url, host))]
+#[builder(require_one_of(token, password), exclusive(token, password))]
+pub struct Connection {
+    url: Option<String>,
+    host: Option<String>,
+    token: Option<String>,
+    password: Option<String>,
+    #[builder(each = "header")]
+    headers: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(require_one_of(names, ids))]
+pub struct Lookup {
+    names: Vec<String>,
+    ids: Vec<u32>,
+}
+
+fn main() {
+    let conn = Connection::builder()
+        .url("http://localhost".to_owned())
+        .token("secret".to_owned())
+        .header("accept".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(conn.url.as_deref(), Some("http://localhost"));
+    assert!(conn.host.is_none());
+    assert_eq!(conn.headers, vec!["accept"]);
+
+    let err = Connection::builder()
+        .url("http://localhost".to_owned())
+        .host("localhost".to_owned())
+        .password("secret".to_owned())
+        .build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err, ConnectionBuilderError::ExclusiveFields(&["url", "host"]));
+    assert_eq!(
+        err.to_string(),
+        "at most one of fields can be set: `url`, `host`"
+    );
+
+    let err = Connection::builder()
+        .host("localhost".to_owned())
+        .build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err, ConnectionBuilderError::RequireOneOf(&["token", "password"]));
+
+    let err = Connection::builder()
+        .token("secret".to_owned())
+        .password("secret".to_owned())
+        .build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err, ConnectionBuilderError::ExclusiveFields(&["token", "password"]));
+
+    let err = Lookup::builder().build().err().unwrap();
+    assert_eq!(err, LookupBuilderError::RequireOneOf(&["names", "ids"]));
+
+    let lookup = Lookup::builder().ids(vec![1, 2]).build().unwrap();
+    assert_eq!(lookup.ids, vec![1, 2]);
+    assert!(lookup.names.is_empty());
+}
@@ -0,0 +1,14 @@
+// Field groups may only refer to the fields of the struct. Misspelled field
+// name should be reported at its span instead of producing code that fails to
+// compile somewhere inside the generated `build` method.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(exclusive(url, hots))]
+pub struct Connection {
+    url: Option<String>,
+    host: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,65 @@
+// Code filling in defaults based on what was already provided needs to look
+// at the state of the builder.
+//
+// Generate methods exposing each of the builder fields:
+//
+//     impl CommandBuilder {
+//         fn executable_ref(&self) -> Option<&String>;
+//         fn is_executable_set(&self) -> bool;
+//         fn clear_executable(&mut self);
+//         ..
+//         fn reset(&mut self);
+//     }
+//
+// For an Option<T> field the getter returns Option<&T>. A Vec field counts as
+// set when it holds at least one element and clearing it removes all of them.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    r#type: u8,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+
+    assert!(!builder.is_executable_set());
+    assert!(builder.executable_ref().is_none());
+    assert!(builder.args_ref().is_none());
+
+    builder.executable("cargo".to_owned()).arg("build".to_owned());
+
+    assert!(builder.is_executable_set());
+    assert_eq!(builder.executable_ref().map(String::as_str), Some("cargo"));
+    assert!(builder.is_args_set());
+    assert_eq!(builder.args_ref(), Some(&vec!["build".to_owned()]));
+
+    if !builder.is_current_dir_set() {
+        builder.current_dir("..".to_owned());
+    }
+    assert_eq!(builder.current_dir_ref().map(String::as_str), Some(".."));
+
+    builder.clear_args();
+    builder.clear_current_dir();
+    assert!(!builder.is_args_set());
+    assert!(!builder.is_current_dir_set());
+
+    builder.r#type(1);
+    assert_eq!(builder.type_ref(), Some(&1));
+
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert!(command.args.is_empty());
+    assert!(command.current_dir.is_none());
+
+    builder.executable("rustc".to_owned()).arg("-O".to_owned());
+    builder.reset();
+    assert!(!builder.is_executable_set());
+    assert!(!builder.is_args_set());
+    assert!(!builder.is_type_set());
+}
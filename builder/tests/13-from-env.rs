@@ -0,0 +1,82 @@
+// Services often take their configuration from environment variables.
+//
+// Look for a container attribute #[builder(env_prefix = "...")] and a field
+// attribute #[builder(env = "...")]. When either one is present generate a
+// constructor of the builder which reads the variables and parses them using
+// FromStr:
+//
+//     impl ServerBuilder {
+//         fn from_env() -> Result<Self, ServerBuilderEnvError> {
+//             ...
+//         }
+//     }
+//
+// With the prefix every field is read from the variable named after the
+// prefix and the field name in uppercase, unless #[builder(env = "...")]
+// names the variable explicitly. Vec fields are split on the separator given
+// by #[builder(env_separator = "...")], which defaults to ",". Variables which
+// are not present leave the field unset so it can still be filled in using
+// the setters.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(env_prefix = "BUILDER_TEST_")]
+pub struct Server {
+    host: String,
+    #[builder(env = "LISTEN_PORT")]
+    port: u16,
+    workers: Option<usize>,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    #[builder(env_separator = ":")]
+    paths: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct Client {
+    #[builder(env = "BUILDER_TEST_CLIENT_RETRIES")]
+    retries: u32,
+    timeout: Option<u64>,
+}
+
+fn main() {
+    std::env::set_var("BUILDER_TEST_HOST", "localhost");
+    std::env::set_var("BUILDER_TEST_LISTEN_PORT", "8080");
+    std::env::set_var("BUILDER_TEST_TAGS", "a,b,,c");
+    std::env::set_var("BUILDER_TEST_PATHS", "/bin:/usr/bin");
+
+    let server = ServerBuilder::from_env()
+        .unwrap()
+        .tag("d".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, None);
+    assert_eq!(server.tags, vec!["a", "b", "c", "d"]);
+    assert_eq!(server.paths, vec!["/bin", "/usr/bin"]);
+
+    std::env::set_var("BUILDER_TEST_WORKERS", "four");
+
+    let err = ServerBuilder::from_env().err().unwrap();
+    assert_eq!(
+        err,
+        ServerBuilderEnvError::Parse {
+            var: "BUILDER_TEST_WORKERS",
+            value: "four".to_owned(),
+            message: "invalid digit found in string".to_owned(),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid value `four` of environment variable `BUILDER_TEST_WORKERS`: invalid digit found in string"
+    );
+
+    std::env::set_var("BUILDER_TEST_CLIENT_RETRIES", "3");
+
+    let client = ClientBuilder::from_env().unwrap().build().unwrap();
+    assert_eq!(client.retries, 3);
+    assert_eq!(client.timeout, None);
+}
@@ -0,0 +1,14 @@
+// As the set of accepted builder options grows, a generic error message stops
+// being helpful. Options which are not recognized should be reported at the
+// span of the misspelled name, together with the closest known option.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(env_prefx = "APP_")]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,65 @@
+// Argument parsers and configuration files hand out (name, value) string
+// pairs. Mark the struct with #[builder(set_by_name)] to generate a setter
+// which looks up the field by its name and parses the value using FromStr:
+//
+//     impl CommandBuilder {
+//         fn set_by_name(
+//             &mut self,
+//             field: &str,
+//             value: &str,
+//         ) -> Result<&mut Self, CommandBuilderSetError> {
+//             ...
+//         }
+//     }
+//
+// Vec fields push one element at a time, under both the field name and the
+// name given with #[builder(each = "...")].
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(set_by_name)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    jobs: Option<u32>,
+    verbose: bool,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+
+    builder
+        .set_by_name("executable", "cargo")
+        .unwrap()
+        .set_by_name("arg", "build")
+        .unwrap()
+        .set_by_name("args", "--release")
+        .unwrap()
+        .set_by_name("jobs", "4")
+        .unwrap()
+        .set_by_name("verbose", "true")
+        .unwrap();
+
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.jobs, Some(4));
+    assert!(command.verbose);
+
+    let err = builder.set_by_name("target", "x86_64").err().unwrap();
+    assert_eq!(err, CommandBuilderSetError::UnknownField("target".to_owned()));
+    assert_eq!(err.to_string(), "unknown field `target`");
+
+    let err = builder.set_by_name("jobs", "many").err().unwrap();
+    assert_eq!(
+        err,
+        CommandBuilderSetError::Parse {
+            field: "jobs",
+            value: "many".to_owned(),
+            message: "invalid digit found in string".to_owned(),
+        }
+    );
+    assert!(!builder.is_jobs_set());
+}
@@ -0,0 +1,121 @@
+// Static configuration tables are built in const and static items, which can
+// only call const fn.
+//
+// Look for a container attribute #[builder(const)]. In this mode the builder
+// follows the owned pattern: setters take the builder by value and return it,
+// and `build` consumes the builder. The `builder`, setter and `build` methods
+// are all const fn.
+//
+// Values with drop glue, like String, can't be dropped in const fn. A value
+// replaced by calling a setter again is forgotten, and `build` never drops the
+// builder or a value taken out of it, also when it returns an error.
+//
+//     impl Route {
+//         const fn builder() -> RouteBuilder;
+//     }
+//
+//     impl RouteBuilder {
+//         const fn path(self, value: &'static str) -> Self;
+//         const fn build(self) -> Result<Route, RouteBuilderError>;
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(const)]
+#[builder(exclusive(redirect, handler))]
+pub struct Route {
+    path: &'static str,
+    method: Option<&'static str>,
+    redirect: Option<&'static str>,
+    handler: Option<fn() -> u16>,
+    weight: u8,
+}
+
+#[derive(Builder)]
+#[builder(const)]
+#[builder(exclusive(note, footer))]
+pub struct Page {
+    title: String,
+    note: Option<String>,
+    footer: Option<String>,
+    #[builder(default_with = "String::new()")]
+    body: String,
+}
+
+const fn ok() -> u16 {
+    200
+}
+
+const fn unwrap(route: Result<Route, RouteBuilderError>) -> Route {
+    match route {
+        Ok(r) => r,
+        Err(_) => panic!("invalid route"),
+    }
+}
+
+static ROUTES: [Route; 2] = [
+    unwrap(Route::builder().path("/").handler(ok).weight(1).build()),
+    unwrap(
+        Route::builder()
+            .path("/old")
+            .method("GET")
+            .redirect("/")
+            .weight(0)
+            .build(),
+    ),
+];
+
+const MISSING: Result<Route, RouteBuilderError> = Route::builder().path("/").build();
+
+const CONFLICT: bool = matches!(
+    Route::builder()
+        .path("/")
+        .weight(0)
+        .redirect("/")
+        .handler(ok)
+        .build(),
+    Err(RouteBuilderError::ExclusiveFields(_))
+);
+
+const EMPTY: Result<Page, PageBuilderError> = Page::builder().title(String::new()).build();
+
+const PAGE_MISSING: Result<Page, PageBuilderError> = Page::builder().note(String::new()).build();
+
+const PAGE_CONFLICT: Result<Page, PageBuilderError> = Page::builder()
+    .title(String::new())
+    .note(String::new())
+    .footer(String::new())
+    .build();
+
+const IS_SET: bool = Route::builder().path("/").is_path_set();
+
+fn main() {
+    assert_eq!(ROUTES[0].path, "/");
+    assert_eq!((ROUTES[0].handler.unwrap())(), 200);
+    assert_eq!(ROUTES[1].method, Some("GET"));
+    assert_eq!(ROUTES[1].redirect, Some("/"));
+
+    assert_eq!(MISSING.err(), Some(RouteBuilderError::MissingField("weight")));
+    assert!(CONFLICT);
+    assert!(IS_SET);
+
+    let empty = EMPTY.unwrap();
+    assert_eq!((empty.title, empty.note, empty.body), (String::new(), None, String::new()));
+    assert_eq!(PAGE_MISSING.err(), Some(PageBuilderError::MissingField("title")));
+    assert_eq!(
+        PAGE_CONFLICT.err(),
+        Some(PageBuilderError::ExclusiveFields(&["note", "footer"])),
+    );
+
+    // Const fn are called at runtime as well.
+    let page = Page::builder()
+        .title("Home".to_owned())
+        .footer("(c)".to_owned())
+        .body("Hello".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(page.title, "Home");
+    assert_eq!(page.footer.as_deref(), Some("(c)"));
+    assert_eq!(page.body, "Hello");
+}
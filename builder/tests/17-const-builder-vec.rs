@@ -0,0 +1,14 @@
+// A Vec can not be grown in a const fn, so the const builder rejects Vec
+// fields instead of generating code that fails to compile.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Route {
+    path: &'static str,
+    #[builder(each = "method")]
+    methods: Vec<&'static str>,
+}
+
+fn main() {}
@@ -0,0 +1,119 @@
+// Functions with many parameters are hard to call correctly, the arguments
+// are easy to mix up and every optional one has to be spelled out as None.
+//
+// Provide an attribute macro #[builder] which turns a function into a builder
+// of its arguments, following the same rules as the derive: Option arguments
+// may be omitted and #[builder(each = "...")] adds one element of a Vec
+// argument at a time.
+//
+//     connect().host("localhost").port(80).call()
+//
+// The `call` method runs the original function once all of the required
+// arguments are set and returns the error otherwise.
+//
+// The attribute applied to an inherent impl block does the same for its
+// methods marked with #[builder], which have to take `&self` or `&mut self`.
+
+use derive_builder::builder;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub struct Connection {
+    address: String,
+    timeout: Option<Duration>,
+    retries: u32,
+    headers: Vec<String>,
+}
+
+#[builder]
+fn connect(
+    host: &str,
+    port: u16,
+    timeout: Option<Duration>,
+    retries: u32,
+    #[builder(each = "header")] headers: Vec<String>,
+) -> Connection {
+    Connection {
+        address: format!("{}:{}", host, port),
+        timeout,
+        retries,
+        headers,
+    }
+}
+
+#[builder(require_one_of(name, id))]
+fn lookup<T: Default>(name: Option<&str>, id: Option<u32>) -> (Option<String>, Option<u32>, T) {
+    (name.map(str::to_owned), id, T::default())
+}
+
+pub struct Client {
+    base: String,
+    sent: Vec<String>,
+}
+
+#[builder]
+impl Client {
+    #[builder]
+    fn url(&self, path: &str, query: Option<&str>) -> String {
+        match query {
+            Some(q) => format!("{}{}?{}", self.base, path, q),
+            None => format!("{}{}", self.base, path),
+        }
+    }
+
+    #[builder]
+    fn send(&mut self, body: String, copies: Option<usize>) -> usize {
+        for _ in 0..copies.unwrap_or(1) {
+            self.sent.push(body.clone());
+        }
+
+        self.sent.len()
+    }
+
+    fn count(&self) -> usize {
+        self.sent.len()
+    }
+}
+
+fn main() {
+    let host = String::from("localhost");
+
+    let conn = connect()
+        .host(&host)
+        .port(80)
+        .retries(3)
+        .header("accept".to_owned())
+        .call()
+        .unwrap();
+
+    assert_eq!(
+        conn,
+        Connection {
+            address: "localhost:80".to_owned(),
+            timeout: None,
+            retries: 3,
+            headers: vec!["accept".to_owned()],
+        }
+    );
+
+    let err = connect().host("localhost").retries(1).call().err().unwrap();
+    assert_eq!(err, ConnectBuilderError::MissingField("port"));
+
+    let found = lookup::<u8>().id(4).call().unwrap();
+    assert_eq!(found, (None, Some(4), 0));
+
+    let err = lookup::<u8>().call().err().unwrap();
+    assert_eq!(err, LookupBuilderError::RequireOneOf(&["name", "id"]));
+
+    let mut client = Client {
+        base: "http://localhost".to_owned(),
+        sent: Vec::new(),
+    };
+
+    let url = client.url().path("/search").query("q=rust").call().unwrap();
+    assert_eq!(url, "http://localhost/search?q=rust");
+
+    let sent = client.send().body("ping".to_owned()).copies(2).call().unwrap();
+    assert_eq!(sent, 2);
+    assert_eq!(client.count(), 2);
+}
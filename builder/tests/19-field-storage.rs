@@ -0,0 +1,65 @@
+// Sometimes the builder should collect a value of different type than the
+// one stored in the field, and convert it when the struct is built.
+//
+// Look for a field attribute #[builder(field(...))] with the following
+// options:
+//
+//   - ty = "..."         type of the value collected by the builder, which is
+//                        also the type taken by the setters;
+//   - build = "..."      expression converting the collected value, which is
+//                        available under the name of the field;
+//   - try_build = "..."  same as above, for a conversion which returns
+//                        Result. The error is reported by `build` as
+//                        InvalidField.
+//
+// The collected value follows the rules of the field type: Vec storage can be
+// filled one element at a time and Option storage may be left unset.
+
+use derive_builder::Builder;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Builder)]
+pub struct Server {
+    #[builder(field(ty = "String", try_build = "addr.parse::<SocketAddr>()"))]
+    addr: SocketAddr,
+    #[builder(each = "tag", field(ty = "Vec<String>", build = "tags.into()"))]
+    tags: Arc<[String]>,
+    #[builder(field(ty = "Option<String>", build = "root.map(PathBuf::from)"))]
+    root: Option<PathBuf>,
+    #[builder(field(build = "workers.max(1)"))]
+    workers: usize,
+}
+
+fn main() {
+    let server = Server::builder()
+        .addr("127.0.0.1:8080".to_owned())
+        .tag("a".to_owned())
+        .tag("b".to_owned())
+        .workers(0)
+        .build()
+        .unwrap();
+
+    assert_eq!(server.addr, "127.0.0.1:8080".parse().unwrap());
+    assert_eq!(&*server.tags, ["a".to_owned(), "b".to_owned()]);
+    assert_eq!(server.root, None);
+    assert_eq!(server.workers, 1);
+
+    let mut builder = Server::builder();
+    builder.root("/srv".to_owned()).workers(4);
+    assert_eq!(builder.root_ref().map(String::as_str), Some("/srv"));
+
+    let err = builder.addr("localhost".to_owned()).build().err().unwrap();
+    assert_eq!(
+        err,
+        ServerBuilderError::InvalidField {
+            field: "addr",
+            message: "invalid socket address syntax".to_owned(),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid value of field `addr`: invalid socket address syntax"
+    );
+}
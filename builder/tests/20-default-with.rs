@@ -0,0 +1,68 @@
+// A field which is not set can get its value computed from other fields.
+//
+// Look for a field attribute #[builder(default_with = "...")]. The expression
+// refers to the final values of other fields by their names and is evaluated
+// only when the field was not set. Fields are resolved in the order required
+// by the expressions, regardless of the order they are declared in. Names
+// bound inside the expression, like closure parameters, are not fields.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Listener {
+    #[builder(default_with = "format!(\"{}:{}\", host, port)")]
+    addr: String,
+    host: String,
+    #[builder(default_with = "if tls { 443 } else { 80 }")]
+    port: u16,
+    #[builder(default_with = "false")]
+    tls: bool,
+    #[builder(each = "alias", default_with = "vec![addr.clone()]")]
+    aliases: Vec<String>,
+}
+
+pub struct Pair {
+    a: u32,
+}
+
+#[derive(Builder)]
+pub struct Totals {
+    #[builder(default_with = "Pair { a: base }.a + 1")]
+    next: u32,
+    base: u32,
+    #[builder(default_with = "items.iter().map(|total| total * 2).sum()")]
+    total: u32,
+    items: Vec<u32>,
+}
+
+fn main() {
+    let listener = Listener::builder()
+        .host("localhost".to_owned())
+        .tls(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(listener.port, 443);
+    assert_eq!(listener.addr, "localhost:443");
+    assert_eq!(listener.aliases, vec!["localhost:443"]);
+
+    let listener = Listener::builder()
+        .host("localhost".to_owned())
+        .addr("0.0.0.0:8080".to_owned())
+        .alias("local".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(listener.port, 80);
+    assert!(!listener.tls);
+    assert_eq!(listener.addr, "0.0.0.0:8080");
+    assert_eq!(listener.aliases, vec!["local"]);
+
+    let err = Listener::builder().build().err().unwrap();
+    assert_eq!(err, ListenerBuilderError::MissingField("host"));
+
+    let totals = Totals::builder().base(1).items(vec![2, 3]).build().unwrap();
+
+    assert_eq!(totals.next, 2);
+    assert_eq!(totals.total, 10);
+}
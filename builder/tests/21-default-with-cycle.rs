@@ -0,0 +1,18 @@
+// Defaults which depend on each other can not be resolved, so the cycle is
+// reported on the first field taking part in it. Fields which only wait on the
+// cycle are not part of it.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Window {
+    #[builder(default_with = "width * height")]
+    area: u32,
+    #[builder(default_with = "height * 2")]
+    width: u32,
+    #[builder(default_with = "width / 2")]
+    height: u32,
+    title: String,
+}
+
+fn main() {}
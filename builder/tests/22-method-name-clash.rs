@@ -0,0 +1,20 @@
+// Every field gets a setter and the `{field}_ref`, `is_{field}_set` and
+// `clear_{field}` methods, and the builder itself has `new`, `reset` and
+// `build`. Fields whose methods would be defined twice are reported on the
+// field instead of failing with duplicate definitions in the generated code.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Timer {
+    interval: u32,
+    reset: bool,
+}
+
+#[derive(Builder)]
+pub struct Cursor {
+    line: u32,
+    line_ref: String,
+}
+
+fn main() {}
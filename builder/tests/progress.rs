@@ -10,4 +10,17 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-field-groups.rs");
+    t.compile_fail("tests/11-field-groups-unknown-field.rs");
+    t.pass("tests/12-introspection.rs");
+    t.pass("tests/13-from-env.rs");
+    t.compile_fail("tests/14-misspelled-option.rs");
+    t.pass("tests/15-set-by-name.rs");
+    t.pass("tests/16-const-builder.rs");
+    t.compile_fail("tests/17-const-builder-vec.rs");
+    t.pass("tests/18-function-builder.rs");
+    t.pass("tests/19-field-storage.rs");
+    t.pass("tests/20-default-with.rs");
+    t.compile_fail("tests/21-default-with-cycle.rs");
+    t.compile_fail("tests/22-method-name-clash.rs");
 }
@@ -0,0 +1,362 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{
+    parse::Error, punctuated::Punctuated, Attribute, Lit, LitStr, Meta, NestedMeta, Path, Token,
+    WherePredicate,
+};
+
+const INVALID_DEBUG_ATTR: &str = r#"
+Expected one of:
+
+    #[debug = "..."]
+    #[debug(skip)]
+    #[debug(skip_if = "path::to::predicate")]
+    #[debug(redact)]
+    #[debug(redact = "partial")]
+    #[debug(with = "path::to::fmt_fn")]
+    #[debug(bound = "T::Item: Debug")]
+    #[debug(max_items = 16)]
+    #[debug(max_len = 256)]
+    #[debug(hex)]
+    #[debug(binary)]
+    #[debug(bytes)]
+    #[debug(flatten)]
+"#;
+
+const INVALID_CONTAINER_ATTR: &str = r#"
+Expected one of:
+
+    #[debug(bound = "T::Assoc: Debug")]
+    #[debug(rename = "...")]
+    #[debug(transparent)]
+    #[debug(non_exhaustive)]
+    #[debug(tree)]
+"#;
+
+// Options given with #[debug(...)] attributes on a struct or an enum.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    // #[debug(rename = "...")]
+    pub rename: Option<LitStr>,
+    // #[debug(transparent)]
+    pub transparent: Option<Path>,
+    // #[debug(non_exhaustive)]
+    pub non_exhaustive: bool,
+    // #[debug(tree)]
+    pub tree: bool,
+}
+
+// Options given with #[debug...] attributes on a field.
+#[derive(Default)]
+pub struct FieldAttrs {
+    // #[debug = "..."]
+    pub format: Option<String>,
+    // #[debug(skip)]
+    pub skip: bool,
+    // #[debug(skip_if = "...")], predicate called with a reference to the field.
+    pub skip_if: Option<Path>,
+    // #[debug(redact)] or #[debug(redact = "...")]
+    pub redact: Option<Redact>,
+    // #[debug(with = "...")], function called as `fn(&T, &mut Formatter) -> fmt::Result`.
+    pub with: Option<Path>,
+    // #[debug(bound = "...")], replaces bounds inferred from the type of the field.
+    // Empty string gives no bounds.
+    pub bound: Option<Vec<WherePredicate>>,
+    // #[debug(max_items = N)], the field is a collection of which only the first N items are printed.
+    pub max_items: Option<usize>,
+    // #[debug(max_len = N)], the field is a string of which only the first N characters are printed.
+    pub max_len: Option<usize>,
+    // #[debug(hex)], #[debug(binary)] or #[debug(bytes)]
+    pub numeric: Option<Numeric>,
+    // #[debug(flatten)], fields of the field are printed in place of it through DebugFields.
+    pub flatten: bool,
+}
+
+// Presentation of integers, or collections of them, given with a field attribute.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Numeric {
+    // #[debug(hex)], integers are printed as `0xff`.
+    Hex,
+    // #[debug(binary)], integers are printed as `0b101`.
+    Binary,
+    // #[debug(bytes)], the field implements AsRef<[u8]> and is printed as a byte string,
+    // or as a hex dump in pretty mode.
+    Bytes,
+}
+
+// How much of a redacted field is printed.
+pub enum Redact {
+    // #[debug(redact)] or #[debug(redact = "full")], only the placeholder is printed.
+    Full,
+    // #[debug(redact = "partial")], the value is a string of which the last 4 characters are
+    // printed if it is long enough, otherwise only its length.
+    Partial,
+}
+
+impl FieldAttrs {
+    // Whether the field is printed through its own Debug implementation.
+    pub fn uses_debug(&self) -> bool {
+        !self.skip
+            && self.redact.is_none()
+            && self.with.is_none()
+            && self.max_len.is_none()
+            && self.numeric.is_none()
+            && !self.flatten
+    }
+
+    // Whether the printed value differs from the Debug output of the field.
+    pub fn is_customized(&self) -> bool {
+        self.redact.is_some()
+            || self.with.is_some()
+            || self.max_items.is_some()
+            || self.max_len.is_some()
+            || self.numeric.is_some()
+            || self.format.is_some()
+    }
+}
+
+// Options giving the printed value of the field, only one of them can be used,
+// as well as #[debug = "..."].
+const PRESENTATION_OPTIONS: &[&str] = &[
+    "redact",
+    "with",
+    "max_items",
+    "max_len",
+    "hex",
+    "binary",
+    "bytes",
+    "flatten",
+];
+
+// Records option given on the field, with whether it gives the printed value.
+// Returns TokenStream with error if the option was given already or if other option
+// giving the printed value was.
+fn record_option(
+    seen: &mut Vec<(String, bool)>,
+    name: String,
+    presentation: bool,
+    tokens: impl ToTokens,
+) -> Result<(), TokenStream> {
+    let msg = if seen.iter().any(|(n, _)| *n == name) {
+        format!("duplicate `{}` option", name)
+    } else if let Some((other, _)) = seen.iter().find(|(_, p)| presentation && *p) {
+        format!("`{}` can not be combined with `{}`", name, other)
+    } else {
+        seen.push((name, presentation));
+        return Ok(());
+    };
+
+    Err(Error::new_spanned(tokens, msg).to_compile_error())
+}
+
+fn invalid(tokens: impl ToTokens) -> TokenStream {
+    Error::new_spanned(tokens, INVALID_DEBUG_ATTR).to_compile_error()
+}
+
+fn get_str(meta: &Meta) -> Result<String, TokenStream> {
+    match meta {
+        Meta::NameValue(nv) => match nv.lit {
+            Lit::Str(ref s) => Ok(s.value()),
+            _ => Err(invalid(meta)),
+        },
+        _ => Err(invalid(meta)),
+    }
+}
+
+fn get_path(meta: &Meta) -> Result<Path, TokenStream> {
+    match meta {
+        Meta::NameValue(nv) => match nv.lit {
+            Lit::Str(ref s) => s.parse::<Path>().map_err(|e| e.to_compile_error()),
+            _ => Err(invalid(meta)),
+        },
+        _ => Err(invalid(meta)),
+    }
+}
+
+fn get_usize(meta: &Meta) -> Result<usize, TokenStream> {
+    match meta {
+        Meta::NameValue(nv) => match nv.lit {
+            Lit::Int(ref i) => i.base10_parse().map_err(|e| e.to_compile_error()),
+            _ => Err(invalid(meta)),
+        },
+        _ => Err(invalid(meta)),
+    }
+}
+
+fn get_predicates(meta: &Meta) -> Result<Vec<WherePredicate>, TokenStream> {
+    use syn::parse::Parser;
+
+    let lit = match meta {
+        Meta::NameValue(nv) => match nv.lit {
+            Lit::Str(ref s) => s,
+            _ => return Err(invalid(meta)),
+        },
+        _ => return Err(invalid(meta)),
+    };
+
+    Punctuated::<WherePredicate, Token![,]>::parse_terminated
+        .parse_str(&lit.value())
+        .map(|p| p.into_iter().collect())
+        .map_err(|e| Error::new_spanned(lit, e).to_compile_error())
+}
+
+pub fn get_field_attrs(attrs: &[Attribute]) -> Result<FieldAttrs, TokenStream> {
+    let mut result = FieldAttrs::default();
+    let mut seen = Vec::new();
+
+    for attr in attrs {
+        if !attr.path.is_ident("debug") {
+            continue;
+        }
+
+        let list = match attr.parse_meta() {
+            Ok(meta @ Meta::NameValue(_)) => {
+                record_option(&mut seen, r#"debug = "...""#.to_owned(), true, attr)?;
+                result.format = Some(get_str(&meta)?);
+                continue;
+            }
+            Ok(Meta::List(l)) => l,
+            _ => return Err(invalid(attr)),
+        };
+
+        for nested in &list.nested {
+            let meta = match nested {
+                NestedMeta::Meta(m) => m,
+                un => return Err(invalid(un)),
+            };
+
+            if let Some(option) = meta.path().get_ident().map(|i| i.to_string()) {
+                let presentation = PRESENTATION_OPTIONS.contains(&option.as_str());
+                record_option(&mut seen, format!("debug({})", option), presentation, meta)?;
+            }
+
+            match meta {
+                Meta::Path(p) if p.is_ident("skip") => result.skip = true,
+                Meta::Path(p) if p.is_ident("redact") => result.redact = Some(Redact::Full),
+                Meta::Path(p) if p.is_ident("hex") => result.numeric = Some(Numeric::Hex),
+                Meta::Path(p) if p.is_ident("binary") => result.numeric = Some(Numeric::Binary),
+                Meta::Path(p) if p.is_ident("bytes") => result.numeric = Some(Numeric::Bytes),
+                Meta::Path(p) if p.is_ident("flatten") => result.flatten = true,
+                Meta::NameValue(nv) if nv.path.is_ident("redact") => {
+                    result.redact = match get_str(meta)?.as_str() {
+                        "full" => Some(Redact::Full),
+                        "partial" => Some(Redact::Partial),
+                        _ => return Err(invalid(meta)),
+                    };
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("skip_if") => {
+                    result.skip_if = Some(get_path(meta)?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("with") => {
+                    result.with = Some(get_path(meta)?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("max_items") => {
+                    result.max_items = Some(get_usize(meta)?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("max_len") => {
+                    result.max_len = Some(get_usize(meta)?);
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("bound") => {
+                    result.bound = Some(get_predicates(meta)?);
+                }
+                _ => return Err(invalid(meta)),
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// Get options out of #[debug(...)] attributes on a struct or an enum.
+// The `bound` option is read by `bounds_from_attrs`.
+pub fn get_container_attrs(attrs: &[Attribute]) -> Result<ContainerAttrs, TokenStream> {
+    let invalid = |tokens: &dyn ToTokens| {
+        Error::new_spanned(tokens, INVALID_CONTAINER_ATTR).to_compile_error()
+    };
+    let mut result = ContainerAttrs::default();
+    let mut non_exhaustive = None;
+
+    for attr in attrs {
+        if !attr.path.is_ident("debug") {
+            continue;
+        }
+
+        let list = match attr.parse_meta() {
+            Ok(Meta::List(l)) => l,
+            _ => return Err(invalid(attr)),
+        };
+
+        for nested in &list.nested {
+            let meta = match nested {
+                NestedMeta::Meta(m) => m,
+                un => return Err(invalid(un)),
+            };
+
+            match meta {
+                Meta::NameValue(nv) if nv.path.is_ident("bound") => {}
+                Meta::NameValue(nv) if nv.path.is_ident("rename") => match nv.lit {
+                    Lit::Str(ref s) => result.rename = Some(s.clone()),
+                    _ => return Err(invalid(meta)),
+                },
+                Meta::Path(p) if p.is_ident("transparent") => result.transparent = Some(p.clone()),
+                Meta::Path(p) if p.is_ident("non_exhaustive") => non_exhaustive = Some(p.clone()),
+                Meta::Path(p) if p.is_ident("tree") => result.tree = true,
+                _ => return Err(invalid(meta)),
+            }
+        }
+    }
+
+    // The transparent struct is printed as its field, without a name or other fields.
+    if result.transparent.is_some() {
+        let conflict = match (&result.rename, &non_exhaustive) {
+            (Some(rename), _) => Some(("rename", rename.to_token_stream())),
+            (None, Some(non_exhaustive)) => {
+                Some(("non_exhaustive", non_exhaustive.to_token_stream()))
+            }
+            (None, None) => None,
+        };
+
+        if let Some((option, tokens)) = conflict {
+            let msg = format!(
+                "`debug({})` can not be combined with `debug(transparent)`",
+                option
+            );
+            return Err(Error::new_spanned(tokens, msg).to_compile_error());
+        }
+    }
+
+    result.non_exhaustive = non_exhaustive.is_some();
+
+    Ok(result)
+}
+
+// Get format string out of #[display("...")] attribute.
+pub fn get_display_format(attrs: &[Attribute]) -> Result<Option<LitStr>, TokenStream> {
+    const INVALID_DISPLAY_ATTR: &str = r#"
+Expected:
+
+    #[display("...")]
+"#;
+
+    let mut result = None;
+
+    for attr in attrs {
+        if !attr.path.is_ident("display") {
+            continue;
+        }
+
+        let list = match attr.parse_meta() {
+            Ok(Meta::List(l)) => l,
+            _ => return Err(Error::new_spanned(attr, INVALID_DISPLAY_ATTR).to_compile_error()),
+        };
+
+        match list.nested.first() {
+            Some(NestedMeta::Lit(Lit::Str(s))) if list.nested.len() == 1 => {
+                result = Some(s.clone())
+            }
+            _ => return Err(Error::new_spanned(attr, INVALID_DISPLAY_ATTR).to_compile_error()),
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,232 @@
+use crate::attrs::get_display_format;
+use crate::util::*;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse::Error, DeriveInput, LitStr};
+
+// Generates Display implementation printing each struct or enum variant with the format
+// string given in #[display("...")]. Unit variants without the attribute print their name.
+pub fn expand(input: &DeriveInput) -> Result<TokenStream, TokenStream> {
+    let data = get_data_debug(input)?;
+
+    let DeriveInput {
+        ident, generics, ..
+    } = input;
+
+    let variants = match data {
+        DataDebug::Struct(ref s) => vec![s],
+        DataDebug::Enum(ref variants) => variants.iter().collect(),
+    };
+
+    let mut arms = Vec::new();
+    let mut referenced = Vec::new();
+
+    for variant in variants {
+        let VariantDebug {
+            name, path, style, ..
+        } = variant;
+
+        let format = match get_display_format(variant.attrs)? {
+            Some(f) => f,
+            None if matches!(style, VariantStyle::Unit)
+                && !matches!(data, DataDebug::Struct(_)) =>
+            {
+                arms.push(quote! { #path => f.write_str(#name), });
+                continue;
+            }
+            None => {
+                // Last token of `Self::Variant` is the name of the variant.
+                let span = match data {
+                    DataDebug::Struct(_) => ident.span(),
+                    DataDebug::Enum(_) => path.clone().into_iter().last().unwrap().span(),
+                };
+                let msg = format!("missing #[display(\"...\")] attribute on `{}`", name);
+                return Err(Error::new(span, msg).to_compile_error());
+            }
+        };
+
+        let (format, uses) = bind_format(&format, &variant.fields)?;
+        let mut fields: Vec<&FieldDebug> = Vec::new();
+
+        for (field, _) in &uses {
+            if !fields.iter().any(|f| f.binding == field.binding) {
+                fields.push(field);
+            }
+        }
+
+        let members = fields.iter().map(|f| &f.member);
+        let bindings = fields.iter().map(|f| &f.binding);
+
+        let pattern = match style {
+            VariantStyle::Unit => quote! { #path },
+            _ => quote! { #path { #(#members: #bindings,)* .. } },
+        };
+
+        arms.push(quote! {
+            #pattern => ::std::write!(f, #format),
+        });
+        referenced.extend(uses);
+    }
+
+    let body = if arms.is_empty() {
+        quote! { match *self {} }
+    } else {
+        quote! { match self { #(#arms)* } }
+    };
+
+    let mut bounds = TokenStream::new();
+
+    for format_trait in [
+        "Display", "Debug", "LowerHex", "UpperHex", "Octal", "Binary", "LowerExp", "UpperExp",
+    ] {
+        let fields = referenced
+            .iter()
+            .filter(|(_, t)| *t == Some(format_trait))
+            .map(|(f, _)| *f)
+            .collect::<Vec<_>>();
+
+        if !fields.is_empty() {
+            let format_trait = syn::Ident::new(format_trait, proc_macro2::Span::call_site());
+            bounds.extend(generate_bounds(
+                &fields,
+                generics,
+                &quote! { ::std::fmt::#format_trait },
+            ));
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let predicates = where_clause
+        .map(|w| w.predicates.iter())
+        .into_iter()
+        .flatten();
+
+    Ok(quote! {
+        impl #impl_generics ::std::fmt::Display for #ident #ty_generics
+        where
+            #(#predicates,)*
+            #bounds
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::result::Result<(), ::std::fmt::Error> {
+                #body
+            }
+        }
+    })
+}
+
+// Formatting trait used by a placeholder with the given spec, for example `Debug` for `{name:?}`.
+fn format_trait(spec: &str) -> &'static str {
+    match spec.chars().last() {
+        Some('?') => "Debug",
+        Some('x') => "LowerHex",
+        Some('X') => "UpperHex",
+        Some('o') => "Octal",
+        Some('b') => "Binary",
+        Some('e') => "LowerExp",
+        Some('E') => "UpperExp",
+        _ => "Display",
+    }
+}
+
+// Fields referred to by a format string, each with the formatting trait it is printed with,
+// or None if it is used as width or precision.
+type FormatUses<'f, 'a> = Vec<(&'f FieldDebug<'a>, Option<&'static str>)>;
+
+// Rewrites placeholders of the format string which refer to fields by name, or by index for
+// tuples, to refer to the names the fields are bound to in the match arm instead. Fields
+// giving the width or precision with `name$` in the spec are rewritten the same way.
+// Returns the rewritten format string and the fields it refers to.
+// Returns TokenStream with error pointing to the format string if a placeholder, width or
+// precision does not name a field, as there are no other arguments to take it from.
+fn bind_format<'f, 'a>(
+    format: &LitStr,
+    fields: &'f [FieldDebug<'a>],
+) -> Result<(LitStr, FormatUses<'f, 'a>), TokenStream> {
+    let value = format.value();
+    let mut chars = value.chars().peekable();
+    let mut result = String::new();
+    let mut referenced = Vec::new();
+
+    let mut bind = |name: &str, format_trait, result: &mut String| {
+        let name = name.trim();
+
+        match fields.iter().find(|f| f.name == name) {
+            Some(field) => {
+                result.push_str(&field.binding.to_string());
+                referenced.push((field, format_trait));
+                Ok(())
+            }
+            None => {
+                let msg = if name.is_empty() {
+                    "placeholders of the format string must name a field, like `{field}`".to_owned()
+                } else {
+                    format!("format string refers to `{}` which is not a field", name)
+                };
+                Err(Error::new(format.span(), msg).to_compile_error())
+            }
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        result.push(c);
+
+        if c != '{' {
+            continue;
+        }
+
+        if chars.peek() == Some(&'{') {
+            result.push(chars.next().unwrap());
+            continue;
+        }
+
+        let mut arg = String::new();
+
+        while let Some(c) = chars.next_if(|c| *c != ':' && *c != '}') {
+            arg.push(c);
+        }
+
+        let mut spec = String::new();
+
+        if chars.next_if_eq(&':').is_some() {
+            while let Some(c) = chars.next_if(|c| *c != '}') {
+                spec.push(c);
+            }
+        }
+
+        bind(&arg, Some(format_trait(&spec)), &mut result)?;
+
+        if spec.is_empty() {
+            continue;
+        }
+
+        result.push(':');
+
+        // Width and precision given by another argument, like `{value:>width$.prec$}`.
+        let mut spec_chars = spec.chars().peekable();
+        let mut word = String::new();
+
+        while let Some(c) = spec_chars.next() {
+            if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+
+                if spec_chars.peek() != Some(&'$') {
+                    continue;
+                }
+
+                bind(&word, None, &mut result)?;
+            } else if c == '.' && spec_chars.peek() == Some(&'*') {
+                let msg = "precision `.*` is not supported, give it with a field like `.field$`";
+                return Err(Error::new(format.span(), msg).to_compile_error());
+            } else {
+                result.push_str(&word);
+                result.push(c);
+            }
+
+            word.clear();
+        }
+
+        result.push_str(&word);
+    }
+
+    Ok((LitStr::new(&result, format.span()), referenced))
+}
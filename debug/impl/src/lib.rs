@@ -0,0 +1,426 @@
+use proc_macro::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, DeriveInput};
+
+mod attrs;
+mod display;
+mod tree;
+mod util;
+mod wrappers;
+
+use attrs::{get_container_attrs, Numeric, Redact};
+use util::*;
+
+#[proc_macro_derive(CustomDebug, attributes(debug))]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    let data = match get_data_debug(&derive_input) {
+        Ok(v) => v,
+        Err(e) => return e.into(),
+    };
+
+    let DeriveInput {
+        ident,
+        generics,
+        attrs,
+        ..
+    } = &derive_input;
+    let container = match get_container_attrs(attrs) {
+        Ok(c) => c,
+        Err(e) => return e.into(),
+    };
+    // Nodes of the fields printed through their own Debug implementation come from
+    // DebugTree, so their type parameters get a DebugTree bound in its implementation.
+    let (bounds, tree_bounds) = match bounds_from_attrs(attrs) {
+        Ok(Some(b)) => (b, quote! {}),
+        Ok(None) => {
+            // A given bound replaces the inferred ones for every printed field, including
+            // fields printed with `with`, `flatten` and the other options.
+            let given = data
+                .all_fields()
+                .into_iter()
+                .filter(|f| !f.attrs.skip)
+                .flat_map(|f| f.attrs.bound.iter().flatten())
+                .collect::<Vec<_>>();
+            let inferred = data
+                .fields()
+                .into_iter()
+                .filter(|f| f.attrs.bound.is_none())
+                .collect::<Vec<_>>();
+            let tree_nodes = inferred
+                .iter()
+                .copied()
+                .filter(|f| !f.attrs.is_customized())
+                .collect::<Vec<_>>();
+            let tree_bounds =
+                generate_bounds(&tree_nodes, generics, &quote! { ::derive_debug::DebugTree });
+            let inferred = generate_bounds(&inferred, generics, &quote! { ::std::fmt::Debug });
+            let flattened = data
+                .all_fields()
+                .into_iter()
+                .filter(|f| f.attrs.flatten && !f.attrs.skip && f.attrs.bound.is_none())
+                .collect::<Vec<_>>();
+            let flattened = generate_bounds(
+                &flattened,
+                generics,
+                &quote! { ::derive_debug::DebugFields },
+            );
+
+            (quote! { #(#given,)* #inferred #flattened }, tree_bounds)
+        }
+        Err(e) => return e.into(),
+    };
+    let body = match data {
+        DataDebug::Struct(ref s) if container.transparent.is_some() => {
+            match debug_transparent(s, container.transparent.as_ref().unwrap()) {
+                Ok(b) => b,
+                Err(e) => return e.into(),
+            }
+        }
+        DataDebug::Struct(ref s) => {
+            let name = container
+                .rename
+                .as_ref()
+                .map(|r| r.value())
+                .unwrap_or_else(|| s.name.clone());
+            let arm = debug_variant(s, &name, container.non_exhaustive);
+            quote! { match self { #arm } }
+        }
+        DataDebug::Enum(_) if container.rename.is_some() || container.transparent.is_some() => {
+            let tokens = container
+                .rename
+                .as_ref()
+                .map(|r| r.to_token_stream())
+                .or_else(|| container.transparent.as_ref().map(|t| t.to_token_stream()));
+
+            return syn::parse::Error::new_spanned(
+                tokens,
+                "`debug(rename)` and `debug(transparent)` are supported only on structs",
+            )
+            .to_compile_error()
+            .into();
+        }
+        DataDebug::Enum(ref variants) if variants.is_empty() => quote! { match *self {} },
+        DataDebug::Enum(ref variants) => {
+            let arms = variants
+                .iter()
+                .map(|v| debug_variant(v, &v.name, container.non_exhaustive));
+            quote! { match self { #(#arms)* } }
+        }
+    };
+
+    let wrappers = wrappers::get_wrappers(&data.all_fields());
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let predicates = where_clause
+        .map(|w| w.predicates.iter())
+        .into_iter()
+        .flatten();
+
+    let predicates = predicates.collect::<Vec<_>>();
+
+    let debug_fields_impl = match data {
+        DataDebug::Struct(ref s)
+            if matches!(s.style, VariantStyle::Named) && container.transparent.is_none() =>
+        {
+            let body = debug_fields(s, container.non_exhaustive);
+            let tree_body = tree::tree_fields(s, container.non_exhaustive);
+
+            quote! {
+                impl #impl_generics ::derive_debug::DebugFields for #ident #ty_generics
+                where
+                    #(#predicates,)*
+                    #bounds
+                {
+                    fn debug_fields(&self, debug: &mut ::std::fmt::DebugStruct<'_, '_>) -> bool {
+                        #wrappers
+                        #body
+                    }
+
+                    fn debug_tree_fields(
+                        &self,
+                        fields: &mut ::std::vec::Vec<(::std::string::String, ::derive_debug::DebugNode)>,
+                    ) -> bool {
+                        #wrappers
+                        #tree_body
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    let debug_tree_impl = if container.tree {
+        let body = tree::expand(&data, &container);
+
+        quote! {
+            impl #impl_generics ::derive_debug::DebugTree for #ident #ty_generics
+            where
+                #(#predicates,)*
+                #bounds
+                #tree_bounds
+            {
+                fn debug_tree(&self) -> ::derive_debug::DebugNode {
+                    #wrappers
+                    #body
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        impl #impl_generics ::std::fmt::Debug for #ident #ty_generics
+        where
+            #(#predicates,)*
+            #bounds
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::result::Result<(), ::std::fmt::Error> {
+                #wrappers
+                #body
+            }
+        }
+
+        #debug_fields_impl
+        #debug_tree_impl
+    }
+    .into()
+}
+
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+
+    match display::expand(&derive_input) {
+        Ok(v) => v.into(),
+        Err(e) => e.into(),
+    }
+}
+
+// Expression giving `&dyn Debug` printed for the field bound in the match arm.
+fn field_value(field: &FieldDebug) -> proc_macro2::TokenStream {
+    let FieldDebug { binding, attrs, .. } = field;
+
+    match attrs.redact {
+        Some(Redact::Full) => return quote! { &::std::format_args!("***") },
+        Some(Redact::Partial) => {
+            return quote! {
+                &::std::format_args!("{}", {
+                    let value: &str = ::std::convert::AsRef::<str>::as_ref(#binding);
+                    let len = value.chars().count();
+
+                    if len >= 12 {
+                        let tail = value.chars().skip(len - 4).collect::<::std::string::String>();
+                        ::std::format!("***{}", tail)
+                    } else {
+                        ::std::format!("*** ({} chars)", len)
+                    }
+                })
+            }
+        }
+        None => {}
+    }
+
+    if let Some(ref with) = attrs.with {
+        return quote! { &__DebugWith(#binding, #with) };
+    }
+
+    if let Some(max_items) = attrs.max_items {
+        return quote! { &__DebugMaxItems(#binding, #max_items) };
+    }
+
+    if let Some(max_len) = attrs.max_len {
+        return quote! { &__DebugMaxLen(#binding, #max_len) };
+    }
+
+    match attrs.numeric {
+        Some(Numeric::Hex) => return quote! { &__DebugRadix(#binding, 16) },
+        Some(Numeric::Binary) => return quote! { &__DebugRadix(#binding, 2) },
+        Some(Numeric::Bytes) => return quote! { &__DebugBytes(#binding) },
+        None => {}
+    }
+
+    match attrs.format {
+        Some(ref d) => quote! { &::std::format_args!(#d, #binding) },
+        None => quote! { #binding },
+    }
+}
+
+// Match arm delegating to the Debug implementation of the only printed field of the struct.
+// Returns TokenStream with error if the struct has other number of fields which are not skipped.
+fn debug_transparent(
+    variant: &VariantDebug,
+    transparent: &syn::Path,
+) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
+    let printed = variant
+        .fields
+        .iter()
+        .filter(|f| !f.attrs.skip)
+        .collect::<Vec<_>>();
+    let field = match printed.as_slice() {
+        [field] => field,
+        _ => {
+            return Err(syn::parse::Error::new_spanned(
+                transparent,
+                "`debug(transparent)` requires a struct with exactly one printed field",
+            )
+            .to_compile_error())
+        }
+    };
+    let path = &variant.path;
+    let patterns = field_patterns(&variant.fields);
+    let value = field_value(field);
+
+    Ok(quote! {
+        match self {
+            #path { #(#patterns,)* } => ::std::fmt::Debug::fmt(#value, f),
+        }
+    })
+}
+
+// Patterns binding the fields of the struct or enum variant, skipped fields are not bound.
+fn field_patterns<'a>(
+    fields: &'a [FieldDebug],
+) -> impl Iterator<Item = proc_macro2::TokenStream> + 'a {
+    fields.iter().map(
+        |FieldDebug {
+             member,
+             binding,
+             attrs,
+             ..
+         }| {
+            if attrs.skip {
+                quote! { #member: _ }
+            } else {
+                quote! { #member: #binding }
+            }
+        },
+    )
+}
+
+// Statements adding the bound fields to the `debug` builder.
+// `debug_mut` is expression giving `&mut DebugStruct` for the flattened fields, which set
+// `omitted` if the flattened struct left out some of its fields.
+fn field_calls<'a>(
+    style: &'a VariantStyle,
+    fields: &'a [FieldDebug],
+    debug_mut: proc_macro2::TokenStream,
+) -> impl Iterator<Item = proc_macro2::TokenStream> + 'a {
+    fields.iter().filter(|f| !f.attrs.skip).map(move |field| {
+        let FieldDebug {
+            binding,
+            name,
+            attrs,
+            ..
+        } = field;
+        let value = field_value(field);
+        let call = match style {
+            VariantStyle::Named if attrs.flatten => {
+                quote! { omitted |= ::derive_debug::DebugFields::debug_fields(#binding, #debug_mut); }
+            }
+            VariantStyle::Named => quote! { debug.field(#name, #value); },
+            _ => quote! { debug.field(#value); },
+        };
+
+        match attrs.skip_if {
+            Some(ref skip_if) => quote! { if !#skip_if(#binding) { #call } },
+            None => call,
+        }
+    })
+}
+
+// Match arm formatting the struct or a single enum variant.
+//
+// `non_exhaustive` ends the output with `..` even if no field was skipped.
+fn debug_variant(
+    variant: &VariantDebug,
+    name: &str,
+    non_exhaustive: bool,
+) -> proc_macro2::TokenStream {
+    let VariantDebug {
+        path,
+        style,
+        fields,
+        ..
+    } = variant;
+    let patterns = field_patterns(fields);
+    let calls = field_calls(style, fields, quote! { &mut debug });
+    let omitted = non_exhaustive || fields.iter().any(|f| f.attrs.skip);
+    let finish = if omitted {
+        quote! { finish_non_exhaustive }
+    } else {
+        quote! { finish }
+    };
+
+    match style {
+        // Whether the flattened structs left out some fields is known only at runtime.
+        VariantStyle::Named if has_flatten(fields) => quote! {
+            #path { #(#patterns,)* } => {
+                let mut debug = f.debug_struct(#name);
+                let mut omitted = #omitted;
+                #(#calls)*
+
+                if omitted {
+                    debug.finish_non_exhaustive()
+                } else {
+                    debug.finish()
+                }
+            }
+        },
+        VariantStyle::Named => quote! {
+            #path { #(#patterns,)* } => {
+                let mut debug = f.debug_struct(#name);
+                #(#calls)*
+                debug.#finish()
+            }
+        },
+        VariantStyle::Unnamed => quote! {
+            #path { #(#patterns,)* } => {
+                let mut debug = f.debug_tuple(#name);
+                #(#calls)*
+                debug.#finish()
+            }
+        },
+        VariantStyle::Unit if non_exhaustive => quote! {
+            #path => f.debug_struct(#name).finish_non_exhaustive(),
+        },
+        VariantStyle::Unit => quote! {
+            #path => f.write_str(#name),
+        },
+    }
+}
+
+// Body of DebugFields::debug_fields adding the printed fields of the struct to `debug`.
+// Returns whether some fields were left out, so that the outer struct ends with `..`.
+fn debug_fields(variant: &VariantDebug, non_exhaustive: bool) -> proc_macro2::TokenStream {
+    let VariantDebug {
+        path,
+        style,
+        fields,
+        ..
+    } = variant;
+    let patterns = field_patterns(fields);
+    let calls = field_calls(style, fields, quote! { debug });
+    let omitted = non_exhaustive || fields.iter().any(|f| f.attrs.skip);
+    let mutability = if has_flatten(fields) {
+        quote! { mut }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        match self {
+            #path { #(#patterns,)* } => {
+                let #mutability omitted = #omitted;
+                #(#calls)*
+                omitted
+            }
+        }
+    }
+}
+
+// Whether some of the printed fields are flattened.
+fn has_flatten(fields: &[FieldDebug]) -> bool {
+    fields.iter().any(|f| f.attrs.flatten && !f.attrs.skip)
+}
@@ -0,0 +1,172 @@
+use crate::attrs::ContainerAttrs;
+use crate::util::*;
+use crate::{field_patterns, field_value};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+// Body of DebugTree::debug_tree giving the same structure as the Debug output.
+//
+// The container attributes have been checked by the Debug implementation already,
+// so `rename` and `transparent` are known to be used on a fitting struct and
+// `transparent` is not combined with `rename` or `non_exhaustive`.
+pub fn expand(data: &DataDebug, container: &ContainerAttrs) -> TokenStream {
+    let body = match data {
+        DataDebug::Struct(s) if container.transparent.is_some() => {
+            let field = s.fields.iter().find(|f| !f.attrs.skip);
+            let patterns = field_patterns(&s.fields);
+            let node = field.map(field_node);
+            let path = &s.path;
+
+            quote! { match self { #path { #(#patterns,)* } => #node } }
+        }
+        DataDebug::Struct(s) => {
+            let name = container
+                .rename
+                .as_ref()
+                .map(|r| r.value())
+                .unwrap_or_else(|| s.name.clone());
+            let arm = tree_variant(s, &name, container.non_exhaustive);
+            quote! { match self { #arm } }
+        }
+        DataDebug::Enum(variants) if variants.is_empty() => quote! { match *self {} },
+        DataDebug::Enum(variants) => {
+            let arms = variants
+                .iter()
+                .map(|v| tree_variant(v, &v.name, container.non_exhaustive));
+            quote! { match self { #(#arms)* } }
+        }
+    };
+
+    quote! {
+        use ::derive_debug::__private::{ViaDebug as _, ViaDebugTree as _};
+        #body
+    }
+}
+
+// Node of the field bound in the match arm. Fields with customized output are
+// leaves holding the printed value, other fields are nodes of their own values.
+// Flattened fields are added by DebugFields::debug_tree_fields instead.
+fn field_node(field: &FieldDebug) -> TokenStream {
+    let FieldDebug { binding, attrs, .. } = field;
+
+    if attrs.is_customized() {
+        let value = field_value(field);
+        quote! { ::derive_debug::DebugNode::Leaf(::std::format!("{:?}", #value)) }
+    } else {
+        quote! { (&::derive_debug::__private::Wrap(#binding)).__debug_tree() }
+    }
+}
+
+// Statements adding nodes of the bound fields to `fields`, or `items` for tuples.
+// `fields_mut` is expression giving `&mut Vec` of the fields for the flattened fields, which
+// set `omitted` if the flattened struct left out some of its fields.
+fn field_pushes<'a>(
+    style: &'a VariantStyle,
+    fields: &'a [FieldDebug],
+    fields_mut: TokenStream,
+) -> impl Iterator<Item = TokenStream> + 'a {
+    fields.iter().filter(|f| !f.attrs.skip).map(move |field| {
+        let FieldDebug {
+            binding,
+            name,
+            attrs,
+            ..
+        } = field;
+        let push = match style {
+            VariantStyle::Named if attrs.flatten => quote! {
+                omitted |= ::derive_debug::DebugFields::debug_tree_fields(#binding, #fields_mut);
+            },
+            VariantStyle::Named => {
+                let node = field_node(field);
+                quote! { fields.push((::std::string::String::from(#name), #node)); }
+            }
+            _ => {
+                let node = field_node(field);
+                quote! { items.push(#node); }
+            }
+        };
+
+        match attrs.skip_if {
+            Some(ref skip_if) => quote! { if !#skip_if(#binding) { #push } },
+            None => push,
+        }
+    })
+}
+
+// Match arm giving the node of the struct or a single enum variant.
+fn tree_variant(variant: &VariantDebug, name: &str, non_exhaustive: bool) -> TokenStream {
+    let VariantDebug {
+        path,
+        style,
+        fields,
+        ..
+    } = variant;
+    let patterns = field_patterns(fields);
+    let non_exhaustive = non_exhaustive || fields.iter().any(|f| f.attrs.skip);
+    let pushes = field_pushes(style, fields, quote! { &mut fields });
+
+    match style {
+        VariantStyle::Named => quote! {
+            #path { #(#patterns,)* } => {
+                #[allow(unused_mut)]
+                let mut fields = ::std::vec::Vec::new();
+                #[allow(unused_mut)]
+                let mut omitted = #non_exhaustive;
+                #(#pushes)*
+                ::derive_debug::DebugNode::Struct {
+                    name: ::std::string::String::from(#name),
+                    fields,
+                    non_exhaustive: omitted,
+                }
+            }
+        },
+        VariantStyle::Unnamed => quote! {
+            #path { #(#patterns,)* } => {
+                #[allow(unused_mut)]
+                let mut items = ::std::vec::Vec::new();
+                #(#pushes)*
+                ::derive_debug::DebugNode::Tuple {
+                    name: ::std::string::String::from(#name),
+                    items,
+                }
+            }
+        },
+        VariantStyle::Unit if non_exhaustive => quote! {
+            #path => ::derive_debug::DebugNode::Struct {
+                name: ::std::string::String::from(#name),
+                fields: ::std::vec::Vec::new(),
+                non_exhaustive: true,
+            },
+        },
+        VariantStyle::Unit => quote! {
+            #path => ::derive_debug::DebugNode::Leaf(::std::string::String::from(#name)),
+        },
+    }
+}
+
+// Body of DebugFields::debug_tree_fields adding nodes of the printed fields of the struct to
+// `fields`. Returns whether some fields were left out.
+pub fn tree_fields(variant: &VariantDebug, non_exhaustive: bool) -> TokenStream {
+    let VariantDebug {
+        path,
+        style,
+        fields,
+        ..
+    } = variant;
+    let patterns = field_patterns(fields);
+    let non_exhaustive = non_exhaustive || fields.iter().any(|f| f.attrs.skip);
+    let pushes = field_pushes(style, fields, quote! { fields });
+
+    quote! {
+        use ::derive_debug::__private::{ViaDebug as _, ViaDebugTree as _};
+
+        match self {
+            #path { #(#patterns,)* } => {
+                #[allow(unused_mut)]
+                let mut omitted = #non_exhaustive;
+                #(#pushes)*
+                omitted
+            }
+        }
+    }
+}
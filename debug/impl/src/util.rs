@@ -0,0 +1,311 @@
+use crate::attrs::{get_field_attrs, FieldAttrs};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse::Error,
+    visit::{self, Visit},
+    Attribute, DeriveInput, Fields, Generics, Lit, Member, Type, TypeBareFn, TypeImplTrait,
+    TypePath, TypeTraitObject,
+};
+
+const INVALID_DERIVE_INPUT: &str = "
+Expected:
+    
+    #[derive(CustomDebug)]
+    struct Name {
+        ..
+    }
+
+or:
+
+    #[derive(CustomDebug)]
+    struct Name(..);
+
+or:
+
+    #[derive(CustomDebug)]
+    enum Name {
+        ..
+    }
+";
+
+const INVALID_DEBUG_BOUND_ATTR: &str = r#"
+Expected:
+
+    #[debug(bound = "T::Assoc: Debug")]
+"#;
+
+const INVALID_FLATTEN: &str = "`debug(flatten)` is supported only on named fields";
+
+macro_rules! err {
+    ($msg:ident, $($i:tt)+) => {
+        Err(Error::new_spanned($($i)+, $msg).to_compile_error())
+    };
+    ($($i:tt)+) => {
+        Err(Error::new_spanned($($i)+, INVALID_DERIVE_INPUT).to_compile_error())
+    }
+}
+
+// Fields of the struct or of a single enum variant.
+pub struct VariantDebug<'a> {
+    // Name printed by the Debug implementation.
+    pub name: String,
+    // Path used to match the value, `Self` for a struct or `Self::Variant` for an enum.
+    pub path: TokenStream,
+    pub style: VariantStyle,
+    pub fields: Vec<FieldDebug<'a>>,
+    // Attributes of the struct or of the enum variant.
+    pub attrs: &'a [Attribute],
+}
+
+// How the fields of a struct or an enum variant are declared.
+pub enum VariantStyle {
+    Named,
+    Unnamed,
+    Unit,
+}
+
+pub enum DataDebug<'a> {
+    Struct(VariantDebug<'a>),
+    Enum(Vec<VariantDebug<'a>>),
+}
+
+impl<'a> DataDebug<'a> {
+    // Get the fields printed through their Debug implementation, other fields need no bounds.
+    pub fn fields(&self) -> Vec<&FieldDebug<'a>> {
+        self.all_fields()
+            .into_iter()
+            .filter(|f| f.attrs.uses_debug())
+            .collect()
+    }
+
+    pub fn all_fields(&self) -> Vec<&FieldDebug<'a>> {
+        match self {
+            DataDebug::Struct(s) => s.fields.iter().collect(),
+            DataDebug::Enum(variants) => variants.iter().flat_map(|v| &v.fields).collect(),
+        }
+    }
+}
+
+pub fn get_data_debug(input: &DeriveInput) -> Result<DataDebug<'_>, TokenStream> {
+    use syn::Data;
+
+    match input.data {
+        Data::Struct(ref d) => Ok(DataDebug::Struct(get_variant_debug(
+            input.ident.to_string(),
+            quote! { Self },
+            &d.fields,
+            &input.attrs,
+        )?)),
+        Data::Enum(ref e) => {
+            let mut variants = Vec::new();
+
+            for v in &e.variants {
+                let ident = &v.ident;
+
+                variants.push(get_variant_debug(
+                    ident.to_string(),
+                    quote! { Self::#ident },
+                    &v.fields,
+                    &v.attrs,
+                )?);
+            }
+
+            Ok(DataDebug::Enum(variants))
+        }
+        _ => err!(input),
+    }
+}
+
+pub struct FieldDebug<'a> {
+    // Field name or index of the field in a tuple.
+    pub member: Member,
+    // Name the field is bound to when the value is matched.
+    pub binding: Ident,
+    pub name: String,
+    pub attrs: FieldAttrs,
+    pub ty: &'a Type,
+}
+
+fn get_variant_debug<'a>(
+    name: String,
+    path: TokenStream,
+    fields: &'a Fields,
+    attrs: &'a [Attribute],
+) -> Result<VariantDebug<'a>, TokenStream> {
+    let style = match fields {
+        Fields::Named(_) => VariantStyle::Named,
+        Fields::Unnamed(_) => VariantStyle::Unnamed,
+        Fields::Unit => VariantStyle::Unit,
+    };
+
+    let mut list = Vec::new();
+
+    for (i, f) in fields.iter().enumerate() {
+        let member = match f.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(i.into()),
+        };
+        let name = match f.ident {
+            Some(ref ident) => ident.to_string(),
+            None => i.to_string(),
+        };
+        let attrs = get_field_attrs(&f.attrs)?;
+
+        if attrs.flatten && f.ident.is_none() {
+            return err!(
+                INVALID_FLATTEN,
+                f.attrs.iter().find(|a| a.path.is_ident("debug"))
+            );
+        }
+
+        list.push(FieldDebug {
+            member,
+            binding: Ident::new(&format!("__field{}", i), Span::call_site()),
+            name,
+            attrs,
+            ty: &f.ty,
+        });
+    }
+
+    Ok(VariantDebug {
+        name,
+        path,
+        style,
+        fields: list,
+        attrs,
+    })
+}
+
+// Collects types which need a Debug bound for a field type to be Debug: type parameters and their
+// associated types, e.g. `T` out of `&'a [T]` or `T::Value` out of `Vec<T::Value>`.
+struct BoundedTypes<'a> {
+    params: Vec<&'a Ident>,
+    found: Vec<TypePath>,
+}
+
+impl<'ast> Visit<'ast> for BoundedTypes<'_> {
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        let first = ty.path.segments.first().map(|s| &s.ident);
+        let last = ty.path.segments.last().map(|s| &s.ident);
+
+        // Qualified path like `<T as Trait>::Assoc` is bounded as a whole if its self type
+        // mentions a type parameter.
+        if let Some(ref qself) = ty.qself {
+            let mut in_qself = BoundedTypes {
+                params: self.params.clone(),
+                found: Vec::new(),
+            };
+            in_qself.visit_type(&qself.ty);
+
+            if !in_qself.found.is_empty() {
+                if !self.found.contains(ty) {
+                    self.found.push(ty.clone());
+                }
+
+                return;
+            }
+        }
+
+        if ty.qself.is_none() && ty.path.leading_colon.is_none() {
+            if let Some(first) = first {
+                if self.params.contains(&first) {
+                    if !self.found.contains(ty) {
+                        self.found.push(ty.clone());
+                    }
+
+                    return;
+                }
+            }
+        }
+
+        // PhantomData is Debug regardless of its parameter.
+        if last.is_some_and(|l| l == "PhantomData") {
+            return;
+        }
+
+        visit::visit_type_path(self, ty);
+    }
+
+    // Function pointers and trait objects are printed the same way regardless of the
+    // types they mention, there is nothing to bound.
+    fn visit_type_bare_fn(&mut self, _: &'ast TypeBareFn) {}
+
+    fn visit_type_trait_object(&mut self, _: &'ast TypeTraitObject) {}
+
+    fn visit_type_impl_trait(&mut self, _: &'ast TypeImplTrait) {}
+}
+
+// Get bounds on `trait_path` inferred from the types of the printed fields.
+pub fn generate_bounds(
+    fields: &[&FieldDebug<'_>],
+    generics: &Generics,
+    trait_path: &TokenStream,
+) -> TokenStream {
+    let mut bounded = BoundedTypes {
+        params: generics.type_params().map(|p| &p.ident).collect(),
+        found: Vec::new(),
+    };
+
+    for field in fields {
+        bounded.visit_type(field.ty);
+    }
+
+    let found = bounded.found;
+
+    quote! { #(#found: #trait_path,)* }
+}
+
+// Get bounds given with #[debug(bound = "...")], these replace the inferred ones.
+// Empty string gives no bounds.
+pub fn bounds_from_attrs(attrs: &[Attribute]) -> Result<Option<TokenStream>, TokenStream> {
+    use syn::parse::Parser;
+    use syn::{punctuated::Punctuated, Meta, NestedMeta, Token, WherePredicate};
+
+    let mut given = false;
+    let mut dbg_clauses: Vec<WherePredicate> = Vec::new();
+
+    for a in attrs.iter() {
+        // Attributes of other macros and doc comments are none of our business.
+        if !a.path.is_ident("debug") {
+            continue;
+        }
+
+        let nm = match a.parse_meta() {
+            Ok(Meta::List(l)) => l,
+            _ => return Err(Error::new_spanned(a, INVALID_DEBUG_BOUND_ATTR).to_compile_error()),
+        };
+
+        for n in nm.nested {
+            let meta = match n {
+                NestedMeta::Meta(Meta::NameValue(m)) if m.path.is_ident("bound") => m,
+                _ => continue,
+            };
+
+            let parsed = match meta.lit {
+                Lit::Str(ref s) => Punctuated::<WherePredicate, Token![,]>::parse_terminated
+                    .parse_str(&s.value())
+                    .ok(),
+                _ => None,
+            };
+
+            match parsed {
+                Some(v) => {
+                    given = true;
+                    dbg_clauses.extend(v);
+                }
+                None => {
+                    return Err(
+                        Error::new_spanned(meta, INVALID_DEBUG_BOUND_ATTR).to_compile_error()
+                    )
+                }
+            }
+        }
+    }
+
+    if !given {
+        return Ok(None);
+    }
+
+    Ok(Some(quote! { #(#dbg_clauses,)* }))
+}
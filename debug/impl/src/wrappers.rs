@@ -0,0 +1,224 @@
+use crate::attrs::Numeric;
+use crate::util::FieldDebug;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+// Get definitions of the wrappers used by the fields, emitted at the start of the `fmt` body.
+pub fn get_wrappers(fields: &[&FieldDebug<'_>]) -> TokenStream {
+    let mut wrappers = TokenStream::new();
+
+    if fields.iter().any(|f| f.attrs.with.is_some()) {
+        wrappers.extend(debug_with());
+    }
+
+    if fields.iter().any(|f| f.attrs.max_items.is_some()) {
+        wrappers.extend(debug_max_items());
+    }
+
+    if fields.iter().any(|f| f.attrs.max_len.is_some()) {
+        wrappers.extend(debug_max_len());
+    }
+
+    let numeric = |n| fields.iter().any(|f| f.attrs.numeric == Some(n));
+
+    if numeric(Numeric::Hex) || numeric(Numeric::Binary) {
+        wrappers.extend(debug_radix());
+    }
+
+    if numeric(Numeric::Bytes) {
+        wrappers.extend(debug_bytes());
+    }
+
+    wrappers
+}
+
+// Wrapper printing a value with the function given in #[debug(with = "...")].
+fn debug_with() -> TokenStream {
+    quote! {
+        struct __DebugWith<'a, T: ?::std::marker::Sized>(
+            &'a T,
+            fn(&T, &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result,
+        );
+
+        impl<'a, T: ?::std::marker::Sized> ::std::fmt::Debug for __DebugWith<'a, T> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+    }
+}
+
+// Wrapper printing the first items of a collection, followed by the total number of items
+// if some were left out.
+fn debug_max_items() -> TokenStream {
+    quote! {
+        struct __DebugMaxItems<'a, T: ?::std::marker::Sized>(&'a T, usize);
+
+        impl<'a, T, E> ::std::fmt::Debug for __DebugMaxItems<'a, T>
+        where
+            T: ?::std::marker::Sized,
+            &'a T: ::std::iter::IntoIterator<Item = &'a E>,
+            E: ::std::fmt::Debug + 'a,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let mut items = ::std::iter::IntoIterator::into_iter(self.0);
+                let mut list = f.debug_list();
+
+                list.entries(::std::iter::Iterator::by_ref(&mut items).take(self.1));
+
+                let rest = ::std::iter::Iterator::count(items);
+
+                if rest > 0 {
+                    list.entry(&::std::format_args!("... ({} items)", self.1 + rest));
+                }
+
+                list.finish()
+            }
+        }
+    }
+}
+
+// Wrapper printing the first characters of a string, followed by the total number of characters
+// if some were left out.
+fn debug_max_len() -> TokenStream {
+    quote! {
+        struct __DebugMaxLen<'a, T: ?::std::marker::Sized>(&'a T, usize);
+
+        impl<'a, T> ::std::fmt::Debug for __DebugMaxLen<'a, T>
+        where
+            T: ?::std::marker::Sized + ::std::convert::AsRef<str>,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let value = self.0.as_ref();
+
+                match value.char_indices().nth(self.1) {
+                    ::std::option::Option::Some((end, _)) => {
+                        ::std::fmt::Debug::fmt(&value[..end], f)?;
+                        ::std::write!(f, "... ({} chars)", value.chars().count())
+                    }
+                    ::std::option::Option::None => ::std::fmt::Debug::fmt(value, f),
+                }
+            }
+        }
+    }
+}
+
+// Wrapper printing integers in base 16 or 2, also inside of slices, arrays, Vec, Option and Box.
+fn debug_radix() -> TokenStream {
+    let ints = [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ]
+    .iter()
+    .map(|t| Ident::new(t, Span::call_site()));
+
+    quote! {
+        struct __DebugRadix<'a, T: ?::std::marker::Sized>(&'a T, u32);
+
+        trait __FmtRadix {
+            fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result;
+        }
+
+        impl<'a, T: ?::std::marker::Sized + __FmtRadix> ::std::fmt::Debug for __DebugRadix<'a, T> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                self.0.fmt_radix(f, self.1)
+            }
+        }
+
+        #(
+            impl __FmtRadix for #ints {
+                fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result {
+                    match radix {
+                        16 => ::std::write!(f, "{:#x}", self),
+                        _ => ::std::write!(f, "{:#b}", self),
+                    }
+                }
+            }
+        )*
+
+        impl<T: __FmtRadix> __FmtRadix for [T] {
+            fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result {
+                f.debug_list()
+                    .entries(self.iter().map(|item| __DebugRadix(item, radix)))
+                    .finish()
+            }
+        }
+
+        impl<T: __FmtRadix, const N: usize> __FmtRadix for [T; N] {
+            fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result {
+                self[..].fmt_radix(f, radix)
+            }
+        }
+
+        impl<T: __FmtRadix> __FmtRadix for ::std::vec::Vec<T> {
+            fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result {
+                self[..].fmt_radix(f, radix)
+            }
+        }
+
+        impl<T: __FmtRadix> __FmtRadix for ::std::option::Option<T> {
+            fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result {
+                match self {
+                    ::std::option::Option::Some(value) => {
+                        f.debug_tuple("Some").field(&__DebugRadix(value, radix)).finish()
+                    }
+                    ::std::option::Option::None => f.write_str("None"),
+                }
+            }
+        }
+
+        impl<T: ?::std::marker::Sized + __FmtRadix> __FmtRadix for ::std::boxed::Box<T> {
+            fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result {
+                (**self).fmt_radix(f, radix)
+            }
+        }
+
+        impl<'b, T: ?::std::marker::Sized + __FmtRadix> __FmtRadix for &'b T {
+            fn fmt_radix(&self, f: &mut ::std::fmt::Formatter<'_>, radix: u32) -> ::std::fmt::Result {
+                (**self).fmt_radix(f, radix)
+            }
+        }
+    }
+}
+
+// Wrapper printing bytes as an escaped byte string, or as a hex dump of 16 bytes per line in
+// pretty mode.
+fn debug_bytes() -> TokenStream {
+    quote! {
+        struct __DebugBytes<'a, T: ?::std::marker::Sized>(&'a T);
+
+        impl<'a, T> ::std::fmt::Debug for __DebugBytes<'a, T>
+        where
+            T: ?::std::marker::Sized + ::std::convert::AsRef<[u8]>,
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let bytes = self.0.as_ref();
+
+                if !f.alternate() {
+                    f.write_str("b\"")?;
+
+                    for b in bytes {
+                        ::std::fmt::Display::fmt(&::std::ascii::escape_default(*b), f)?;
+                    }
+
+                    return f.write_str("\"");
+                }
+
+                let mut list = f.debug_list();
+
+                for (i, chunk) in bytes.chunks(16).enumerate() {
+                    let mut hex = ::std::string::String::new();
+                    let mut ascii = ::std::string::String::new();
+
+                    for b in chunk {
+                        hex.push_str(&::std::format!("{:02x} ", b));
+                        ascii.push(if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' });
+                    }
+
+                    list.entry(&::std::format_args!("{:08x}: {:48} |{}|", i * 16, hex, ascii));
+                }
+
+                list.finish()
+            }
+        }
+    }
+}
@@ -1,57 +1,227 @@
-use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+// Crates that have the "proc-macro" crate type are only allowed to export
+// procedural macros, so the traits implemented by the generated code are
+// defined in this crate and the derive macros are defined in the
+// derive_debug_impl crate and re-exported from here. Users need to import only
+// this one crate.
+pub use derive_debug_impl::{CustomDebug, CustomDisplay};
 
-mod util;
+use std::fmt;
 
-use util::*;
+// Implemented by #[derive(CustomDebug)] for structs with named fields. Adds
+// the printed fields of the struct to a `debug_struct` builder of another
+// struct, which is how #[debug(flatten)] inlines them. Returns whether some
+// fields were left out, so that the other struct ends its output with `..`.
+//
+// `debug_tree_fields` does the same for the fields of a DebugNode::Struct, so
+// flattened structs are inlined in the DebugTree output even if they don't
+// implement DebugTree themselves.
+pub trait DebugFields {
+    fn debug_fields(&self, debug: &mut fmt::DebugStruct<'_, '_>) -> bool;
 
-#[proc_macro_derive(CustomDebug, attributes(debug))]
-pub fn derive(input: TokenStream) -> TokenStream {
-    let derive_input = parse_macro_input!(input as DeriveInput);
-    let data_struct = match get_data_struct(&derive_input) {
-        Ok(v) => v,
-        Err(e) => return e.into(),
-    };
-    let fields = match get_fields_debug(data_struct) {
-        Ok(f) => f,
-        Err(e) => return e.into(),
+    fn debug_tree_fields(&self, fields: &mut Vec<(String, DebugNode)>) -> bool;
+}
+
+// Structured form of the Debug output of a value, so that tests can compare
+// two values field by field or render them in another format without parsing
+// the `{:#?}` output. Values of types without their own structure are leaves
+// holding the Debug output of the value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DebugNode {
+    Struct {
+        name: String,
+        fields: Vec<(String, DebugNode)>,
+        non_exhaustive: bool,
+    },
+    Tuple {
+        name: String,
+        items: Vec<DebugNode>,
+    },
+    List(Vec<DebugNode>),
+    Map(Vec<(DebugNode, DebugNode)>),
+    Leaf(String),
+}
+
+// Implemented by #[derive(CustomDebug)] with #[debug(tree)] on the container,
+// and below for the standard types printed as lists, maps or tuples.
+//
+// Fields whose type doesn't implement DebugTree become leaves with their Debug
+// output. This is decided when the derived impl is compiled, not for each use,
+// so fields using a type parameter give it a DebugTree bound, like the Debug
+// bound of the Debug impl. Type parameters get no such bound, and always
+// become leaves, if #[debug(bound = "...")] is given on the field or on the
+// container, and in the fields added by DebugFields::debug_tree_fields.
+pub trait DebugTree: fmt::Debug {
+    fn debug_tree(&self) -> DebugNode;
+}
+
+macro_rules! leaf_tree {
+    ($($ty:ty),*) => {
+        $(
+            impl DebugTree for $ty {
+                fn debug_tree(&self) -> DebugNode {
+                    DebugNode::Leaf(format!("{:?}", self))
+                }
+            }
+        )*
     };
+}
+
+leaf_tree! {
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+    f32, f64, bool, char, str, String, ()
+}
+
+impl<T: DebugTree + ?Sized> DebugTree for &T {
+    fn debug_tree(&self) -> DebugNode {
+        (**self).debug_tree()
+    }
+}
 
-    let DeriveInput {
-        ident,
-        generics,
-        attrs,
-        ..
-    } = &derive_input;
-    let fmt_name = ident.to_string();
-    let where_clause =
-        where_clause_from_attrs(attrs).unwrap_or_else(|| generate_where_clause(&fields, generics));
-    let debug_field_calls = proc_macro2::TokenStream::from_iter(fields.iter().map(
-        |FieldDebug {
-             ident, name, debug, ..
-         }| {
-            let value = debug
-                .as_ref()
-                .map(|d| quote! { &::std::format_args!(#d, &self.#ident) })
-                .unwrap_or_else(|| quote! { &self.#ident });
-
-            quote! {
-                .field(#name, #value)
+impl<T: DebugTree + ?Sized> DebugTree for &mut T {
+    fn debug_tree(&self) -> DebugNode {
+        (**self).debug_tree()
+    }
+}
+
+impl<T: DebugTree + ?Sized> DebugTree for Box<T> {
+    fn debug_tree(&self) -> DebugNode {
+        (**self).debug_tree()
+    }
+}
+
+impl<T: DebugTree + ?Sized> DebugTree for std::rc::Rc<T> {
+    fn debug_tree(&self) -> DebugNode {
+        (**self).debug_tree()
+    }
+}
+
+impl<T: DebugTree + ?Sized> DebugTree for std::sync::Arc<T> {
+    fn debug_tree(&self) -> DebugNode {
+        (**self).debug_tree()
+    }
+}
+
+impl<T: DebugTree> DebugTree for Option<T> {
+    fn debug_tree(&self) -> DebugNode {
+        match self {
+            Some(value) => DebugNode::Tuple {
+                name: String::from("Some"),
+                items: vec![value.debug_tree()],
+            },
+            None => DebugNode::Leaf(String::from("None")),
+        }
+    }
+}
+
+macro_rules! list_tree {
+    ($($ty:ident)*) => {
+        $(
+            impl<T: DebugTree> DebugTree for std::collections::$ty<T> {
+                fn debug_tree(&self) -> DebugNode {
+                    DebugNode::List(self.iter().map(DebugTree::debug_tree).collect())
+                }
             }
-        },
-    ));
-
-    let generics_unbounded = strip_bounds_from_generics(generics);
-
-    quote! {
-        impl #generics ::std::fmt::Debug for #ident #generics_unbounded #where_clause {
-            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::result::Result<(), ::std::fmt::Error> {
-               f
-                   .debug_struct(#fmt_name)
-                   #debug_field_calls
-                   .finish()
+        )*
+    };
+}
+
+list_tree! { VecDeque LinkedList BTreeSet HashSet }
+
+impl<T: DebugTree> DebugTree for [T] {
+    fn debug_tree(&self) -> DebugNode {
+        DebugNode::List(self.iter().map(DebugTree::debug_tree).collect())
+    }
+}
+
+impl<T: DebugTree, const N: usize> DebugTree for [T; N] {
+    fn debug_tree(&self) -> DebugNode {
+        self[..].debug_tree()
+    }
+}
+
+impl<T: DebugTree> DebugTree for Vec<T> {
+    fn debug_tree(&self) -> DebugNode {
+        self[..].debug_tree()
+    }
+}
+
+impl<K: DebugTree, V: DebugTree> DebugTree for std::collections::BTreeMap<K, V> {
+    fn debug_tree(&self) -> DebugNode {
+        DebugNode::Map(
+            self.iter()
+                .map(|(k, v)| (k.debug_tree(), v.debug_tree()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: DebugTree, V: DebugTree, S> DebugTree for std::collections::HashMap<K, V, S> {
+    fn debug_tree(&self) -> DebugNode {
+        DebugNode::Map(
+            self.iter()
+                .map(|(k, v)| (k.debug_tree(), v.debug_tree()))
+                .collect(),
+        )
+    }
+}
+
+macro_rules! tuple_tree {
+    ($(($($name:ident)+))*) => {
+        $(
+            impl<$($name: DebugTree),+> DebugTree for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn debug_tree(&self) -> DebugNode {
+                    let ($($name,)+) = self;
+                    DebugNode::Tuple {
+                        name: String::new(),
+                        items: vec![$($name.debug_tree()),+],
+                    }
+                }
             }
+        )*
+    };
+}
+
+tuple_tree! {
+    (A)
+    (A B)
+    (A B C)
+    (A B C D)
+    (A B C D E)
+    (A B C D E F)
+}
+
+// Used by the generated code, not part of the public API.
+//
+// Fields are turned into nodes with `(&Wrap(field)).__debug_tree()`. Method
+// resolution picks `ViaDebugTree` if the type of the field implements
+// DebugTree, and falls back to `ViaDebug` giving a leaf with the Debug output
+// otherwise, so fields of other types don't need any annotation.
+#[doc(hidden)]
+pub mod __private {
+    use super::{DebugNode, DebugTree};
+    use std::fmt::Debug;
+
+    pub struct Wrap<'a, T: ?Sized>(pub &'a T);
+
+    pub trait ViaDebugTree {
+        fn __debug_tree(&self) -> DebugNode;
+    }
+
+    impl<T: DebugTree + ?Sized> ViaDebugTree for Wrap<'_, T> {
+        fn __debug_tree(&self) -> DebugNode {
+            self.0.debug_tree()
+        }
+    }
+
+    pub trait ViaDebug {
+        fn __debug_tree(&self) -> DebugNode;
+    }
+
+    impl<T: Debug + ?Sized> ViaDebug for &Wrap<'_, T> {
+        fn __debug_tree(&self) -> DebugNode {
+            DebugNode::Leaf(format!("{:?}", self.0))
         }
-    }.into()
+    }
 }
@@ -0,0 +1,49 @@
+// Support enums as well. The Debug implementation matches on self and formats
+// each kind of variant the same way as the standard derive does:
+//
+//   - unit variants are printed as just their name;
+//   - tuple variants with f.debug_tuple(...);
+//   - struct variants with f.debug_struct(...).
+//
+// Field attributes #[debug = "..."] and inference of the trait bounds work the
+// same as for structs, looking at the fields of every variant.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub enum Event<T> {
+    Started,
+    Progress(u32, #[debug = "{:.1}%"] f64),
+    Finished {
+        result: T,
+        #[debug = "0x{:x}"]
+        code: u8,
+    },
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn main() {
+    let started = Event::<String>::Started;
+    assert_eq!(format!("{:?}", started), "Started");
+
+    let progress = Event::<String>::Progress(3, 42.25);
+    assert_eq!(format!("{:?}", progress), "Progress(3, 42.2%)");
+
+    let finished = Event::Finished {
+        result: "done".to_owned(),
+        code: 255,
+    };
+    assert_eq!(
+        format!("{:?}", finished),
+        r#"Finished { result: "done", code: 0xff }"#,
+    );
+    assert_eq!(
+        format!("{:#?}", finished),
+        "Finished {\n    result: \"done\",\n    code: 0xff,\n}",
+    );
+
+    fn assert_debug<F: std::fmt::Debug>() {}
+    assert_debug::<Never>();
+}
@@ -0,0 +1,21 @@
+// Tuple structs are printed with f.debug_tuple(...) and unit structs as just
+// their name, the same as the standard derive does. Elements of a tuple struct
+// accept the #[debug = "..."] attribute like the named fields.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Id(#[debug = "#{:06}"] u64);
+
+#[derive(CustomDebug)]
+pub struct Pair<T>(T, &'static str);
+
+#[derive(CustomDebug)]
+pub struct Marker;
+
+fn main() {
+    assert_eq!(format!("{:?}", Id(42)), "Id(#000042)");
+    assert_eq!(format!("{:?}", Pair(1.5, "x")), r#"Pair(1.5, "x")"#);
+    assert_eq!(format!("{:#?}", Pair(1, "x")), "Pair(\n    1,\n    \"x\",\n)");
+    assert_eq!(format!("{:?}", Marker), "Marker");
+}
@@ -0,0 +1,66 @@
+// Some fields should never be printed, like large caches or callbacks which do
+// not even implement Debug. Accept #[debug(skip)] on a field to leave it out.
+// The output ends with `..` as with finish_non_exhaustive() so that the reader
+// can tell something was omitted.
+//
+// Accept #[debug(skip_if = "path")] to leave out a field depending on its
+// value. The path names a function called with a reference to the field, the
+// field is printed if it returns false.
+//
+// Skipped fields are not printed, so the type parameters used only by them do
+// not need a Debug bound.
+
+use derive_debug::CustomDebug;
+
+pub struct NotDebug;
+
+#[derive(CustomDebug)]
+pub struct Cache<T, F> {
+    name: &'static str,
+    #[debug(skip_if = "Option::is_none")]
+    limit: Option<usize>,
+    #[debug(skip)]
+    entries: Vec<T>,
+    #[debug(skip)]
+    on_evict: F,
+}
+
+#[derive(CustomDebug)]
+pub struct Handle(u32, #[debug(skip)] NotDebug);
+
+#[derive(CustomDebug)]
+pub enum Slot {
+    Empty,
+    Taken {
+        #[debug(skip_if = "str::is_empty")]
+        owner: &'static str,
+    },
+}
+
+fn main() {
+    let cache = Cache {
+        name: "users",
+        limit: None,
+        entries: vec![NotDebug],
+        on_evict: |_: NotDebug| {},
+    };
+    assert_eq!(format!("{:?}", cache), r#"Cache { name: "users", .. }"#);
+
+    let cache = Cache {
+        limit: Some(10),
+        ..cache
+    };
+    assert_eq!(
+        format!("{:?}", cache),
+        r#"Cache { name: "users", limit: Some(10), .. }"#,
+    );
+
+    assert_eq!(format!("{:?}", Handle(7, NotDebug)), "Handle(7, ..)");
+
+    assert_eq!(format!("{:?}", Slot::Empty), "Empty");
+    assert_eq!(format!("{:?}", Slot::Taken { owner: "" }), "Taken");
+    assert_eq!(
+        format!("{:?}", Slot::Taken { owner: "me" }),
+        r#"Taken { owner: "me" }"#,
+    );
+}
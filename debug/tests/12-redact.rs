@@ -0,0 +1,43 @@
+// Secrets must not end up in logs through Debug. Accept #[debug(redact)] on a
+// field to print a fixed placeholder `***` instead of its value.
+//
+// With #[debug(redact = "partial")] the field must be a string, that is
+// implement AsRef<str>. Its last 4 characters are printed after the
+// placeholder if the string has at least 12 characters, so that most of it
+// stays hidden, otherwise only its length is printed.
+//
+// Redacted fields are not printed with Debug, so they add no Debug bounds.
+
+use derive_debug::CustomDebug;
+
+pub struct Key(Vec<u8>);
+
+#[derive(CustomDebug)]
+pub struct Credentials<K> {
+    user: &'static str,
+    #[debug(redact)]
+    password: String,
+    #[debug(redact = "partial")]
+    token: String,
+    #[debug(redact = "partial")]
+    pin: &'static str,
+    #[debug(redact)]
+    key: K,
+}
+
+fn main() {
+    let credentials = Credentials {
+        user: "admin",
+        password: "hunter2".to_owned(),
+        token: "ghp_0123456789abcdef".to_owned(),
+        pin: "1234",
+        key: Key(vec![1, 2, 3]),
+    };
+
+    assert_eq!(
+        format!("{:?}", credentials),
+        r#"Credentials { user: "admin", password: ***, token: ***cdef, pin: *** (4 chars), key: *** }"#,
+    );
+    assert!(!format!("{:#?}", credentials).contains("hunter2"));
+    assert_eq!(credentials.key.0.len(), 3);
+}
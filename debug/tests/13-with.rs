@@ -0,0 +1,64 @@
+// Some field types have no helpful Debug implementation, or none at all. Accept
+// #[debug(with = "path")] on a field to print it with a function
+//
+//     fn(&T, &mut fmt::Formatter) -> fmt::Result
+//
+// called through a wrapper which implements Debug, so that the type of the
+// field does not need to implement Debug itself and adds no bound.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+use std::time::Duration;
+
+pub struct Handle(i32);
+
+mod format {
+    use std::fmt;
+    use std::time::Duration;
+
+    pub fn millis(d: &Duration, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}ms", d.as_millis())
+    }
+
+    pub fn count<T>(items: &Vec<T>, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{} items]", items.len())
+    }
+}
+
+fn handle(h: &Handle, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "fd:{}", h.0)
+}
+
+#[derive(CustomDebug)]
+pub struct Task<T> {
+    #[debug(with = "format::millis")]
+    timeout: Duration,
+    #[debug(with = "handle")]
+    handle: Handle,
+    #[debug(with = "format::count")]
+    queue: Vec<T>,
+}
+
+#[derive(CustomDebug)]
+pub enum State {
+    Idle,
+    Waiting(#[debug(with = "format::millis")] Duration),
+}
+
+fn main() {
+    let task = Task {
+        timeout: Duration::from_millis(1500),
+        handle: Handle(3),
+        queue: vec![Handle(4), Handle(5)],
+    };
+    assert_eq!(
+        format!("{:?}", task),
+        "Task { timeout: 1500ms, handle: fd:3, queue: [2 items] }",
+    );
+
+    assert_eq!(format!("{:?}", State::Idle), "Idle");
+    assert_eq!(
+        format!("{:?}", State::Waiting(Duration::from_secs(2))),
+        "Waiting(2000ms)",
+    );
+}
@@ -0,0 +1,69 @@
+// Accept options on the struct or enum itself:
+//
+//   - #[debug(rename = "...")] sets the name printed for a struct;
+//   - #[debug(transparent)] on a struct with a single field prints just the
+//     field, delegating to its Debug implementation. Skipped fields don't
+//     count, so markers like PhantomData can sit next to the field;
+//   - #[debug(non_exhaustive)] always ends the output with `..`, as if some
+//     field was skipped.
+
+use derive_debug::CustomDebug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+#[debug(rename = "User")]
+pub struct UserRecordV2 {
+    id: u32,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u32);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Hex {
+    #[debug = "0x{:x}"]
+    value: u32,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Meters<T> {
+    value: f64,
+    #[debug(skip)]
+    _unit: PhantomData<T>,
+}
+
+pub struct Metric;
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive, rename = "Config")]
+pub struct ConfigInner {
+    name: &'static str,
+}
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive)]
+pub enum Message {
+    Ping,
+    Data(u8),
+}
+
+fn main() {
+    assert_eq!(format!("{:?}", UserRecordV2 { id: 1 }), "User { id: 1 }");
+    assert_eq!(format!("{:?}", UserId(7)), "7");
+    assert_eq!(format!("{:?}", Some(UserId(7))), "Some(7)");
+    assert_eq!(format!("{:?}", Hex { value: 255 }), "0xff");
+    let meters = Meters::<Metric> {
+        value: 1.5,
+        _unit: PhantomData,
+    };
+    assert_eq!(format!("{:?}", meters), "1.5");
+    assert_eq!(
+        format!("{:?}", ConfigInner { name: "app" }),
+        r#"Config { name: "app", .. }"#,
+    );
+    assert_eq!(format!("{:?}", Message::Ping), "Ping { .. }");
+    assert_eq!(format!("{:?}", Message::Data(1)), "Data(1, ..)");
+}
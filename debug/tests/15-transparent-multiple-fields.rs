@@ -0,0 +1,12 @@
+// A transparent struct prints only its field, so it must have exactly one.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+// Infer the Debug bounds by looking at every part of a field type, not only at
+// the arguments of a path. References, tuples, arrays and slices are Debug if
+// the types inside them are, so type parameters found there need a bound.
+//
+// Function pointers, trait objects and PhantomData are printed the same way
+// whatever types they mention, so parameters used only there need no bound. A
+// trait object is Debug only through its own trait bounds.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub struct Shapes<'a, A, B, C, D, E> {
+    by_ref: &'a A,
+    pair: (B, u8),
+    array: [C; 2],
+    boxed: Box<[D]>,
+    nested: Option<&'a [(E, E)]>,
+}
+
+#[derive(CustomDebug)]
+pub struct Callbacks<T, U, V, W> {
+    convert: fn(T) -> U,
+    source: Box<dyn Source<V>>,
+    marker: PhantomData<(W, [W; 4])>,
+}
+
+pub trait Source<T>: Debug {
+    fn next(&mut self) -> Option<T>;
+}
+
+pub struct NotDebug;
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Shapes<u8, u16, u32, u64, i8>>();
+    assert_debug::<Callbacks<NotDebug, NotDebug, NotDebug, NotDebug>>();
+
+    let shapes = Shapes {
+        by_ref: &1,
+        pair: ("b", 2),
+        array: ['c'; 2],
+        boxed: vec![4].into_boxed_slice(),
+        nested: Some(&[(5, 6)]),
+    };
+    assert_eq!(
+        format!("{:?}", shapes),
+        r#"Shapes { by_ref: 1, pair: ("b", 2), array: ['c', 'c'], boxed: [4], nested: Some([(5, 6)]) }"#,
+    );
+}
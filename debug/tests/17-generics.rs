@@ -0,0 +1,66 @@
+// Generate a valid impl for any generics of the input: lifetimes, const
+// generics, parameters with defaults and where clauses. The impl header can
+// not repeat the defaults, which syn's Generics::split_for_impl takes care of.
+//
+// A qualified path like `<T as Trait>::Assoc` is bounded as a whole, so that
+// the parameter itself does not need to implement Debug.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Assoc;
+}
+
+#[derive(CustomDebug)]
+pub struct Buffer<'a, T: 'a = u8, const N: usize = 4>
+where
+    T: Copy,
+{
+    items: [T; N],
+    name: &'a str,
+}
+
+#[derive(CustomDebug)]
+pub struct Qualified<T: Trait> {
+    value: <T as Trait>::Assoc,
+    values: Vec<<T as Trait>::Assoc>,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Assoc: Debug")]
+pub struct EscapeHatch<T>
+where
+    T: Trait,
+{
+    value: T::Assoc,
+}
+
+pub struct NotDebug;
+
+impl Trait for NotDebug {
+    type Assoc = u8;
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Buffer<u16, 2>>();
+    assert_debug::<Qualified<NotDebug>>();
+    assert_debug::<EscapeHatch<NotDebug>>();
+
+    let buffer: Buffer = Buffer {
+        items: [1, 2, 3, 4],
+        name: "b",
+    };
+    assert_eq!(
+        format!("{:?}", buffer),
+        r#"Buffer { items: [1, 2, 3, 4], name: "b" }"#,
+    );
+
+    let qualified = Qualified::<NotDebug> {
+        value: 1,
+        values: vec![2],
+    };
+    assert_eq!(format!("{:?}", qualified), "Qualified { value: 1, values: [2] }");
+}
@@ -0,0 +1,84 @@
+// Next to CustomDebug, provide a CustomDisplay derive. The format string is
+// given with #[display("...")] on the struct, or on each enum variant, and
+// refers to the fields by name, or by index for tuples:
+//
+//     #[derive(CustomDisplay)]
+//     #[display("{name} ({id})")]
+//     pub struct User {
+//         name: String,
+//         id: u32,
+//     }
+//
+// Unit variants without the attribute are printed as their name. Type
+// parameters get a bound only if they are used by the fields which the format
+// string refers to, and the bound is the formatting trait of the placeholder,
+// like Debug for `{name:?}`. Width and precision can be taken from fields with
+// `{value:>width$}`.
+
+use derive_debug::CustomDisplay;
+use std::fmt::Display;
+
+#[derive(CustomDisplay)]
+#[display("{name} ({id:>04})")]
+pub struct User<T> {
+    name: T,
+    id: u32,
+    secret: Vec<T>,
+}
+
+#[derive(CustomDisplay)]
+#[display("{0}:{1}")]
+pub struct Addr(&'static str, u16);
+
+#[derive(CustomDisplay)]
+pub enum Shape<L> {
+    #[display("circle r={radius}")]
+    Circle { radius: f32 },
+    #[display("line {0} to {1}, {{L}}")]
+    Line(L, L),
+    Empty,
+}
+
+#[derive(CustomDisplay)]
+#[display("{label:?}={value:>width$.prec$} ({value:e})")]
+pub struct Reading<T> {
+    label: T,
+    value: f64,
+    width: usize,
+    prec: usize,
+}
+
+#[derive(Debug)]
+pub struct DebugOnly;
+
+pub struct NotDisplay;
+
+fn assert_display<F: Display>() {}
+
+fn main() {
+    let user = User {
+        name: "ann",
+        id: 7,
+        secret: vec![],
+    };
+    assert_eq!(user.to_string(), "ann (0007)");
+    assert_eq!(user.secret.len(), 0);
+
+    assert_eq!(Addr("localhost", 80).to_string(), "localhost:80");
+
+    assert_eq!(Shape::<u8>::Circle { radius: 1.5 }.to_string(), "circle r=1.5");
+    assert_eq!(Shape::Line(1, 2).to_string(), "line 1 to 2, {L}");
+    assert_eq!(Shape::<u8>::Empty.to_string(), "Empty");
+
+    let reading = Reading {
+        label: DebugOnly,
+        value: 1.5,
+        width: 6,
+        prec: 2,
+    };
+    assert_eq!(reading.to_string(), "DebugOnly=  1.50 (1.5e0)");
+
+    assert_display::<User<&str>>();
+    assert_display::<Reading<DebugOnly>>();
+    assert_display::<Shape<u8>>();
+}
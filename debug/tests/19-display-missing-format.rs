@@ -0,0 +1,13 @@
+// Variants with fields have no obvious way to be displayed, so they require a
+// format string.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+pub enum Token {
+    #[display("number {0}")]
+    Number(i64),
+    Word(String),
+}
+
+fn main() {}
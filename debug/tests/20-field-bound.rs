@@ -0,0 +1,112 @@
+// The bound given with #[debug(bound = "...")] on the struct replaces all of
+// the inferred bounds. Accept the attribute on a field as well, replacing only
+// the bounds inferred from the type of that field. An empty string means that
+// the struct or the field adds no bounds at all. Fields printed with `with` or
+// `flatten` can give the bounds their formatting needs the same way.
+//
+//     impl<T: Trait, U> Debug for Wrapper<T, U>
+//     where
+//         T::Value: Debug,
+//         U: Debug,
+//     {...}
+
+use derive_debug::{CustomDebug, DebugFields};
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U, V> {
+    #[debug(bound = "T::Value: Debug")]
+    field: Field<T>,
+    normal: U,
+    #[debug(bound = "")]
+    tag: Tag<V>,
+}
+
+#[derive(CustomDebug)]
+pub struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+// Debug regardless of its parameter, which the inference can not know.
+pub struct Tag<V>(PhantomData<V>);
+
+impl<V> Debug for Tag<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Tag")
+    }
+}
+
+fn show<T: Clone + Debug>(value: &T, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "<{:?}>", value.clone())
+}
+
+#[derive(CustomDebug)]
+pub struct Shown<T> {
+    #[debug(with = "show", bound = "T: Clone + Debug")]
+    value: T,
+}
+
+#[derive(CustomDebug)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+pub struct Outer<T> {
+    id: u8,
+    #[debug(flatten, bound = "T: DebugFields")]
+    inner: T,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "")]
+pub struct Tagged<V> {
+    tag: Tag<V>,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: Debug,")]
+pub struct Listed<T> {
+    items: Vec<T>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id, u8, Id>>();
+    assert_debug::<Tagged<Id>>();
+
+    let wrapper = Wrapper::<Id, u8, Id> {
+        field: Field { values: vec![1] },
+        normal: 2,
+        tag: Tag(PhantomData),
+    };
+    assert_eq!(
+        format!("{:?}", wrapper),
+        "Wrapper { field: Field { values: [1] }, normal: 2, tag: Tag }",
+    );
+
+    assert_eq!(format!("{:?}", Shown { value: 3 }), "Shown { value: <3> }");
+
+    let outer = Outer {
+        id: 1,
+        inner: Point { x: 2, y: 3 },
+    };
+    assert_eq!(format!("{:?}", outer), "Outer { id: 1, x: 2, y: 3 }");
+
+    let tagged = Tagged::<Id> { tag: Tag(PhantomData) };
+    assert_eq!(format!("{:?}", tagged), "Tagged { tag: Tag }");
+    assert_eq!(format!("{:?}", Listed { items: vec![1] }), "Listed { items: [1] }");
+}
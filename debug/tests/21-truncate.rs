@@ -0,0 +1,36 @@
+// Large collections and long strings flood the logs. Accept
+// #[debug(max_items = N)] on a field which is a collection, that is a
+// reference to it implements IntoIterator<Item = &T>, and
+// #[debug(max_len = N)] on a field which is a string, implementing
+// AsRef<str>. Only the first N items or characters are printed, followed by
+// the total count if something was left out. Collections are printed as a
+// list whatever their kind.
+
+use derive_debug::CustomDebug;
+use std::collections::BTreeSet;
+
+#[derive(CustomDebug)]
+pub struct Batch {
+    #[debug(max_items = 3)]
+    ids: Vec<u32>,
+    #[debug(max_items = 2)]
+    tags: BTreeSet<&'static str>,
+    #[debug(max_len = 5)]
+    body: String,
+    #[debug(max_len = 5)]
+    title: &'static str,
+}
+
+fn main() {
+    let batch = Batch {
+        ids: (0..100_000).collect(),
+        tags: ["a", "b"].into_iter().collect(),
+        body: "ééééééé".to_owned(),
+        title: "short",
+    };
+
+    assert_eq!(
+        format!("{:?}", batch),
+        r#"Batch { ids: [0, 1, 2, ... (100000 items)], tags: ["a", "b"], body: "ééééé"... (7 chars), title: "short" }"#,
+    );
+}
@@ -0,0 +1,59 @@
+// Accept shortcuts for printing integers and bytes:
+//
+//   - #[debug(hex)] prints integers as 0xff;
+//   - #[debug(binary)] prints integers as 0b101;
+//   - #[debug(bytes)] prints a field implementing AsRef<[u8]> as an escaped
+//     byte string, or as a hex dump of 16 bytes per line in pretty mode.
+//
+// Unlike #[debug = "..."], hex and binary also apply to integers inside of
+// slices, arrays, Vec, Option and Box, and follow the {:#?} pretty mode.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Packet<'a> {
+    #[debug(hex)]
+    id: u32,
+    #[debug(binary)]
+    flags: u8,
+    #[debug(hex)]
+    checksums: Vec<Option<u16>>,
+    #[debug(hex)]
+    header: &'a [u8; 2],
+    #[debug(bytes)]
+    payload: Vec<u8>,
+}
+
+fn main() {
+    let packet = Packet {
+        id: 255,
+        flags: 0b101,
+        checksums: vec![Some(0xbeef), None],
+        header: &[1, 2],
+        payload: b"GET /\r\n\x00\"".to_vec(),
+    };
+
+    assert_eq!(
+        format!("{:?}", packet),
+        r#"Packet { id: 0xff, flags: 0b101, checksums: [Some(0xbeef), None], header: [0x1, 0x2], payload: b"GET /\r\n\x00\"" }"#,
+    );
+
+    let expected = r#"Packet {
+    id: 0xff,
+    flags: 0b101,
+    checksums: [
+        Some(
+            0xbeef,
+        ),
+        None,
+    ],
+    header: [
+        0x1,
+        0x2,
+    ],
+    payload: [
+        00000000: 47 45 54 20 2f 0d 0a 00 22                       |GET /..."|,
+    ],
+}"#;
+    assert_eq!(format!("{:#?}", packet), expected);
+}
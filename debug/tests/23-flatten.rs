@@ -0,0 +1,80 @@
+// Structs composed out of smaller ones print deeply nested output. Accept
+// #[debug(flatten)] on a named field to print the fields of the inner struct
+// directly in the outer one.
+//
+// This needs a trait next to the derive macro, so the derive is re-exported
+// from a runtime crate like in the bitfield project. CustomDebug implements
+// derive_debug::DebugFields for structs with named fields, which adds their
+// fields to the DebugStruct builder of another struct. A flattened field of a
+// generic type gets a DebugFields bound instead of a Debug bound.
+//
+// The trait method returns whether the inner struct left out some fields, with
+// #[debug(skip)] or #[debug(non_exhaustive)], and the outer struct then ends
+// its output with `..` like for its own skipped fields.
+
+use derive_debug::{CustomDebug, DebugFields};
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Server {
+    host: &'static str,
+    #[debug(flatten)]
+    limits: Limits,
+    #[debug(flatten)]
+    extra: Extra<u8>,
+}
+
+#[derive(CustomDebug)]
+pub struct Limits {
+    #[debug = "{}s"]
+    timeout: u32,
+    #[debug(skip)]
+    cache: Vec<u8>,
+    #[debug(flatten)]
+    nested: Nested,
+}
+
+#[derive(CustomDebug)]
+pub struct Nested {
+    retries: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Extra<T> {
+    value: T,
+}
+
+#[derive(CustomDebug)]
+pub struct Generic<T> {
+    #[debug(flatten)]
+    inner: T,
+}
+
+fn assert_debug<F: Debug>() {}
+fn assert_debug_fields<F: DebugFields>() {}
+
+fn main() {
+    let server = Server {
+        host: "localhost",
+        limits: Limits {
+            timeout: 30,
+            cache: vec![],
+            nested: Nested { retries: 3 },
+        },
+        extra: Extra { value: 1 },
+    };
+
+    assert_eq!(
+        format!("{:?}", server),
+        r#"Server { host: "localhost", timeout: 30s, retries: 3, value: 1, .. }"#,
+    );
+
+    let generic = Generic {
+        inner: Nested { retries: 5 },
+    };
+    assert_eq!(format!("{:?}", generic), "Generic { retries: 5 }");
+    assert_eq!(server.limits.cache.len(), 0);
+
+    assert_debug::<Generic<Nested>>();
+    assert_debug_fields::<Generic<Nested>>();
+}
@@ -0,0 +1,13 @@
+// Fields of the inner struct have names, which a tuple struct can not print.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Outer(#[debug(flatten)] Inner);
+
+#[derive(CustomDebug)]
+pub struct Inner {
+    value: u8,
+}
+
+fn main() {}
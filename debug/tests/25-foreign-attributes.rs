@@ -0,0 +1,51 @@
+// Doc comments and attributes of other macros can be placed next to
+// #[derive(CustomDebug)] and must be left alone. Only #[debug(...)] attributes
+// are checked, and the options of several of them are combined.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+/// A documented struct.
+#[derive(CustomDebug, Clone)]
+#[repr(C)]
+#[allow(dead_code)]
+#[debug(rename = "Documented")]
+#[debug(bound = "T::Value: Debug")]
+#[debug(non_exhaustive)]
+pub struct Wrapper<T: Trait> {
+    /// Documented field.
+    #[allow(unused)]
+    value: T::Value,
+}
+
+/// A documented enum.
+#[derive(CustomDebug)]
+#[must_use]
+pub enum Choice {
+    /// Documented variant.
+    Yes,
+    No,
+}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    impl Clone for Id {
+        fn clone(&self) -> Self {
+            Id
+        }
+    }
+
+    let wrapper = Wrapper::<Id> { value: 1 };
+    assert_eq!(format!("{:?}", wrapper), "Documented { value: 1, .. }");
+    assert_eq!(format!("{:?}", Choice::Yes), "Yes");
+    assert_eq!(format!("{:?}", Choice::No), "No");
+}
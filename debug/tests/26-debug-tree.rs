@@ -0,0 +1,170 @@
+// With #[debug(tree)] on the container, CustomDebug also implements DebugTree,
+// giving the Debug output as a DebugNode that test tooling can compare field
+// by field or render as JSON without parsing the `{:#?}` output.
+//
+// Skipped fields are left out and fields with customized output are leaves
+// holding the printed value. Other fields are nodes of their own values:
+// standard collections become lists and maps, types deriving DebugTree give
+// their own structure, and values of any other Debug types become leaves.
+// Fields of a generic type get a DebugTree bound on the type parameter, and
+// flattened structs are inlined whether they derive DebugTree or not.
+
+use derive_debug::{CustomDebug, DebugNode, DebugTree};
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+pub struct Opaque(u8);
+
+#[derive(CustomDebug)]
+#[debug(tree)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+#[debug(tree)]
+pub struct Account<T> {
+    name: String,
+    #[debug(redact)]
+    password: String,
+    #[debug = "{:#x}"]
+    flags: u8,
+    #[debug(skip)]
+    cache: Vec<u8>,
+    origin: Point,
+    tags: Vec<&'static str>,
+    limits: BTreeMap<&'static str, Option<u32>>,
+    opaque: Opaque,
+    extra: T,
+}
+
+#[derive(CustomDebug)]
+pub struct Margin {
+    left: u8,
+    #[debug(skip)]
+    cached: bool,
+}
+
+#[derive(CustomDebug)]
+#[debug(tree)]
+pub struct Label {
+    #[debug(flatten)]
+    at: Point,
+    #[debug(flatten)]
+    margin: Margin,
+    text: &'static str,
+}
+
+#[derive(CustomDebug)]
+#[debug(tree)]
+pub enum Shape {
+    Circle(Point, u32),
+    Empty,
+}
+
+fn leaf(value: &str) -> DebugNode {
+    DebugNode::Leaf(value.to_owned())
+}
+
+fn point(x: i32, y: i32) -> DebugNode {
+    DebugNode::Struct {
+        name: "Point".to_owned(),
+        fields: vec![("x".to_owned(), leaf(&x.to_string())), ("y".to_owned(), leaf(&y.to_string()))],
+        non_exhaustive: false,
+    }
+}
+
+fn main() {
+    let mut limits = BTreeMap::new();
+    limits.insert("daily", Some(10));
+    limits.insert("total", None);
+
+    let account = Account {
+        name: "root".to_owned(),
+        password: "hunter2".to_owned(),
+        flags: 255,
+        cache: vec![1, 2, 3],
+        origin: Point { x: 1, y: -2 },
+        tags: vec!["admin"],
+        limits,
+        opaque: Opaque(7),
+        extra: (1u8, 'c'),
+    };
+
+    let expected = DebugNode::Struct {
+        name: "Account".to_owned(),
+        fields: vec![
+            ("name".to_owned(), leaf("\"root\"")),
+            ("password".to_owned(), leaf("***")),
+            ("flags".to_owned(), leaf("0xff")),
+            ("origin".to_owned(), point(1, -2)),
+            ("tags".to_owned(), DebugNode::List(vec![leaf("\"admin\"")])),
+            (
+                "limits".to_owned(),
+                DebugNode::Map(vec![
+                    (
+                        leaf("\"daily\""),
+                        DebugNode::Tuple {
+                            name: "Some".to_owned(),
+                            items: vec![leaf("10")],
+                        },
+                    ),
+                    (leaf("\"total\""), leaf("None")),
+                ]),
+            ),
+            ("opaque".to_owned(), leaf("Opaque(7)")),
+            (
+                "extra".to_owned(),
+                DebugNode::Tuple {
+                    name: String::new(),
+                    items: vec![leaf("1"), leaf("'c'")],
+                },
+            ),
+        ],
+        non_exhaustive: true,
+    };
+    assert_eq!(account.debug_tree(), expected);
+
+    let circle = Shape::Circle(Point { x: 0, y: 0 }, 5);
+    assert_eq!(
+        circle.debug_tree(),
+        DebugNode::Tuple {
+            name: "Circle".to_owned(),
+            items: vec![point(0, 0), leaf("5")],
+        }
+    );
+    assert_eq!(Shape::Empty.debug_tree(), leaf("Empty"));
+
+    // Fields of flattened structs are inlined, and the skipped field of the
+    // flattened Margin makes the Label non-exhaustive, like `..` in Debug.
+    let label = Label {
+        at: Point { x: 3, y: 4 },
+        margin: Margin {
+            left: 1,
+            cached: false,
+        },
+        text: "here",
+    };
+    assert_eq!(
+        label.debug_tree(),
+        DebugNode::Struct {
+            name: "Label".to_owned(),
+            fields: vec![
+                ("x".to_owned(), leaf("3")),
+                ("y".to_owned(), leaf("4")),
+                ("left".to_owned(), leaf("1")),
+                ("text".to_owned(), leaf("\"here\"")),
+            ],
+            non_exhaustive: true,
+        }
+    );
+    assert_eq!(
+        format!("{:?}", label),
+        r#"Label { x: 3, y: 4, left: 1, text: "here", .. }"#,
+    );
+    assert!(!label.margin.cached);
+
+    // The Debug output is not changed.
+    assert_eq!(format!("{:?}", circle), "Circle(Point { x: 0, y: 0 }, 5)");
+}
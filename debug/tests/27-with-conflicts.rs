@@ -0,0 +1,37 @@
+// Fields printed with `redact` or `with` show only what that option gives, so
+// other options choosing how the field is printed would be ignored. They are
+// rejected instead, in the same attribute or in separate ones.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+fn fmt_len(value: &Vec<u8>, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} bytes", value.len())
+}
+
+#[derive(CustomDebug)]
+pub struct Secret {
+    #[debug(redact, with = "fmt_len")]
+    token: Vec<u8>,
+}
+
+#[derive(CustomDebug)]
+pub struct Items {
+    #[debug(with = "fmt_len", max_items = 4)]
+    items: Vec<u8>,
+}
+
+#[derive(CustomDebug)]
+pub struct Text {
+    #[debug(max_len = 8)]
+    #[debug(with = "fmt_len")]
+    text: Vec<u8>,
+}
+
+#[derive(CustomDebug)]
+pub struct Flags {
+    #[debug(hex, with = "fmt_len")]
+    flags: Vec<u8>,
+}
+
+fn main() {}
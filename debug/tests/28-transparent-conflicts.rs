@@ -0,0 +1,17 @@
+// A transparent struct is printed as its only field, so there is no name to
+// rename and no list of fields to end with `..`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(transparent, rename = "Id")]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive)]
+#[debug(transparent)]
+pub struct Token {
+    value: String,
+}
+
+fn main() {}
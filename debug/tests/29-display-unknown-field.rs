@@ -0,0 +1,38 @@
+// Placeholders of the display format string take their values from the fields
+// of the struct or the variant, there are no positional arguments. Placeholders
+// which name no field are pointed out on the format string.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("user {}")]
+pub struct Anonymous {
+    name: String,
+}
+
+#[derive(CustomDisplay)]
+#[display("user {0}")]
+pub struct Positional {
+    name: String,
+}
+
+#[derive(CustomDisplay)]
+#[display("user {nmae}")]
+pub struct Misspelled {
+    name: String,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value:>width$}")]
+pub struct Width {
+    value: f64,
+    with: usize,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value:.*}")]
+pub struct Precision {
+    value: f64,
+}
+
+fn main() {}
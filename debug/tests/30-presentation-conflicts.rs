@@ -0,0 +1,39 @@
+// A field is printed with one presentation at a time, so the options choosing
+// it exclude each other, and an option given twice is a mistake as well. Both
+// are rejected instead of silently using one of the values.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Registers {
+    #[debug(hex, max_items = 4)]
+    values: Vec<u32>,
+}
+
+#[derive(CustomDebug)]
+pub struct Mask {
+    #[debug(hex, binary)]
+    bits: u16,
+}
+
+#[derive(CustomDebug)]
+pub struct Packet {
+    #[debug(bytes)]
+    #[debug(hex)]
+    payload: Vec<u8>,
+}
+
+#[derive(CustomDebug)]
+pub struct Flags {
+    #[debug = "{:#x}"]
+    #[debug(binary)]
+    flags: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Message {
+    #[debug(max_len = 16, max_len = 32)]
+    text: String,
+}
+
+fn main() {}
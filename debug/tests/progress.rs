@@ -9,4 +9,26 @@ fn tests() {
     t.pass("tests/06-bound-trouble.rs");
     t.pass("tests/07-associated-type.rs");
     t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-enum.rs");
+    t.pass("tests/10-tuple-and-unit-struct.rs");
+    t.pass("tests/11-skip.rs");
+    t.pass("tests/12-redact.rs");
+    t.pass("tests/13-with.rs");
+    t.pass("tests/14-container-options.rs");
+    t.compile_fail("tests/15-transparent-multiple-fields.rs");
+    t.pass("tests/16-bounds-through-types.rs");
+    t.pass("tests/17-generics.rs");
+    t.pass("tests/18-display.rs");
+    t.compile_fail("tests/19-display-missing-format.rs");
+    t.pass("tests/20-field-bound.rs");
+    t.pass("tests/21-truncate.rs");
+    t.pass("tests/22-numeric.rs");
+    t.pass("tests/23-flatten.rs");
+    t.compile_fail("tests/24-flatten-tuple.rs");
+    t.pass("tests/25-foreign-attributes.rs");
+    t.pass("tests/26-debug-tree.rs");
+    t.compile_fail("tests/27-with-conflicts.rs");
+    t.compile_fail("tests/28-transparent-conflicts.rs");
+    t.compile_fail("tests/29-display-unknown-field.rs");
+    t.compile_fail("tests/30-presentation-conflicts.rs");
 }